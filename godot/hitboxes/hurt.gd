@@ -1,5 +1,13 @@
 extends Area2D
 
+func _ready() -> void:
+	ObjectManager.register(self)
+
+func _exit_tree() -> void:
+	ObjectManager.unregister(self)
+	# Registers again in _ready when it is added back to a tree
+	request_ready()
+
 func _on_area_entered(area: Area2D) -> void:
 	var player_hitbox := area as PlayerHitbox
 	if player_hitbox:
@@ -5,12 +5,36 @@ class_name Ring extends Area2D
 
 @export var sprite: AnimatedSprite2D
 var collected := false
+## Players that already collected this ring, only used in competition with per player rings
+var collected_by: Array[Character] = []
+
+func _ready() -> void:
+	ObjectManager.register(self)
+
+func _exit_tree() -> void:
+	ObjectManager.unregister(self)
+	# Registers again in _ready when it is added back to a tree
+	request_ready()
+
 func _on_area_entered(area: Area2D) -> void:
 	if collected:
 		return
 	var player_hitbox := area as PlayerHitbox
 	if player_hitbox and player_hitbox.can_gather_rings():
-		player_hitbox.increment_rings(amount)
+		var competition := get_tree().get_first_node_in_group("competition") as Competition
+		if competition and competition.get_per_player_rings():
+			var player := player_hitbox.player
+			if player in collected_by:
+				return
+			collected_by.append(player)
+			player_hitbox.increment_rings(amount)
+			if collected_by.size() < competition.players.size():
+				# Keep the ring visible only for the players that didn't collect it yet
+				visibility_layer = competition.visibility_layers_without(collected_by)
+				sprite.visibility_layer = visibility_layer
+				return
+		else:
+			player_hitbox.increment_rings(amount)
 		sprite.play("collected")
 		collected = true
 		await sprite.animation_finished
@@ -10,6 +10,10 @@ static func move_toward_positioni(origin: Vector2, target: Vector2, speed: float
 	origin = floor(origin)
 	return Vector2(move_toward(target.x, origin.x, speed), move_toward(target.y, origin.y, speed))
 
+## Follows the player after it moved and collided this frame
+func _ready() -> void:
+	process_physics_priority = ObjectManager.PIPELINE_PRIORITY + 1
+
 func _physics_process(_delta: float) -> void:
 	if player.is_grounded:
 		drag_top_margin = 0.0
@@ -0,0 +1,9 @@
+## Finish line of the competition, every player that touches it finishes the race
+extends Area2D
+
+@export var competition: Competition
+
+func _on_area_entered(area: Area2D) -> void:
+	var player_hitbox := area as PlayerHitbox
+	if player_hitbox:
+		competition.reach_goal(player_hitbox.player)
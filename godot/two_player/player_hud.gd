@@ -0,0 +1,14 @@
+## Time, rings and lives of a single player in the competition
+extends Label
+
+@export var competition: Competition
+@export var player_index := 0
+
+func _process(_delta: float) -> void:
+	var player: Character = competition.players[player_index]
+	var frames := competition.get_time(player_index)
+	@warning_ignore("integer_division")
+	var minutes := frames / 3600
+	@warning_ignore("integer_division")
+	var seconds := (frames / 60) % 60
+	text = "TIME %d:%02d\nRINGS %d\nLIVES %d" % [minutes, seconds, player.rings, competition.get_lives(player_index)]
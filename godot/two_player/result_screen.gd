@@ -0,0 +1,25 @@
+## Shows the results of each player and the winner when the race is over
+extends Control
+
+@export var competition: Competition
+@export var label: Label
+
+func _ready() -> void:
+	hide()
+	competition.race_finished.connect(_on_race_finished)
+
+func _on_race_finished(results: Array[Dictionary], winner: int) -> void:
+	var text := ""
+	for i in results.size():
+		var result := results[i]
+		var frames: int = result.time
+		@warning_ignore("integer_division")
+		var time := "%d:%02d" % [frames / 3600, (frames / 60) % 60] if result.reached_goal else "--:--"
+		text += "PLAYER %d  TIME %s  RINGS %d  TOTAL %d\n" % [i + 1, time, result.rings, result.total_rings]
+	text += "\nDRAW" if winner < 0 else "\nPLAYER %d WINS" % (winner + 1)
+	label.text = text
+	show()
+
+func _unhandled_input(event: InputEvent) -> void:
+	if visible and event.is_action_pressed("restart"):
+		get_tree().reload_current_scene()
@@ -0,0 +1,18 @@
+## Two player split screen, like the 2P versus mode of Sonic 2
+## Every viewport shares the same world, each player camera renders to its own viewport
+extends VBoxContainer
+
+@export var competition: Competition
+@export var viewports: Array[SubViewport]
+
+func _ready() -> void:
+	var world := viewports[0].world_2d
+	for i in viewports.size():
+		var viewport := viewports[i]
+		viewport.world_2d = world
+		# Hide the objects that only the other players can see (like rings they haven't collected)
+		viewport.canvas_cull_mask = competition.canvas_cull_mask(i)
+		var player: Character = competition.players[i]
+		var camera: Camera2D = player.get_node("Camera2D")
+		camera.custom_viewport = viewport
+		camera.make_current()
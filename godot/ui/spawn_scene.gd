@@ -16,11 +16,11 @@ func use_tool() -> void:
 	node.global_position = pos
 	var spring := node as Spring
 	if spring:
-		spring.direction = tool_direction as Spring.Direction
+		spring.direction = tool_direction
 		node.position -= spring.sprite.position
-	var spike := node as Spike
-	if spike:
-		spike.direction = tool_direction as Spike.Direction
+	var hazard := node as HazardObject
+	if hazard:
+		hazard.direction = tool_direction
 
 
 	objects.add_child(node)
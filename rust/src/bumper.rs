@@ -0,0 +1,168 @@
+#![allow(clippy::needless_pass_by_value)]
+pub mod flipper;
+
+use std::f32::consts::TAU;
+
+use godot::{classes::AnimatedSprite2D, prelude::*};
+
+use crate::{character::Character, object_manager::InteractiveObject};
+
+#[derive(GodotConvert, Var, Export, Default, Debug, PartialEq, Eq, Clone, Copy)]
+#[godot(via = GString)]
+pub enum BumperShape {
+    /// Spring Yard Zone bumper (Sonic 1), bounces away from its center
+    #[default]
+    Round,
+    /// Casino Night Zone triangle bumper (Sonic 2), bounces away from its face,
+    /// which points up before rotating the node
+    Triangle,
+}
+
+/// Angles are calculated with 256 steps per turn in the original games
+fn hex_angle(angle: f32) -> f32 {
+    (angle / TAU * 256.0).round() * TAU / 256.0
+}
+
+/// From <https://info.sonicretro.org/SPG:Game_Objects#Bumpers>
+/// Bounces the characters touching its hitbox, even when they are rolling or jumping.
+/// The first `max_scoring_bumps` bumps emit `scored`, the score is kept by the game
+#[derive(GodotClass)]
+#[class(init, base=Node2D)]
+pub struct Bumper {
+    #[export]
+    shape: BumperShape,
+    /// Radius of the square hitbox
+    #[export]
+    #[init(val = 8.0)]
+    radius: f32,
+    /// Speed of the characters after bouncing
+    #[export]
+    #[init(val = 7.0)]
+    bounce_speed: f32,
+    /// Frames during which a character that just bounced is ignored
+    #[export]
+    #[init(val = 8)]
+    cooldown: i32,
+    /// Points awarded with each scoring bump
+    #[export]
+    #[init(val = 10)]
+    points: i32,
+    /// Each bumper only gives points the first 10 times in Sonic 1, a negative value removes the limit
+    #[export]
+    #[init(val = 10)]
+    max_scoring_bumps: i32,
+    #[export]
+    sprite: Option<Gd<AnimatedSprite2D>>,
+    /// Bumps that gave points so far
+    bumps: i32,
+    /// Characters that bounced recently and the frames left until they can bounce again
+    cooldowns: Vec<(Gd<Character>, i32)>,
+    /// True when registered with an `ObjectManager`, which checks the characters
+    managed: bool,
+    base: Base<Node2D>,
+}
+
+#[godot_api]
+impl INode2D for Bumper {
+    fn ready(&mut self) {
+        let finished = self.base().callable("on_animation_finished");
+        if let Some(sprite) = &mut self.sprite {
+            sprite.connect("animation_finished", &finished);
+        }
+        self.register();
+    }
+    fn exit_tree(&mut self) {
+        self.unregister();
+    }
+    fn physics_process(&mut self, _delta: f64) {
+        self.process_unmanaged();
+    }
+}
+
+impl InteractiveObject for Bumper {
+    fn node(&self) -> Gd<Node2D> {
+        self.base().clone()
+    }
+    fn is_managed(&self) -> bool {
+        self.managed
+    }
+    fn set_managed(&mut self, value: bool) {
+        self.managed = value;
+    }
+    fn interact_players(&mut self, players: &[Gd<Character>]) {
+        for (_, frames) in &mut self.cooldowns {
+            *frames -= 1;
+        }
+        self.cooldowns.retain(|(_, frames)| *frames > 0);
+
+        let position = self.base().get_global_position();
+        let hitbox = Rect2::new(
+            position - Vector2::splat(self.radius),
+            Vector2::splat(self.radius * 2.0),
+        );
+        for player in players {
+            if self.cooldowns.iter().any(|(other, _)| other == player) {
+                continue;
+            }
+            if hitbox.intersects(player.bind().hitbox_rect()) {
+                self.bump(player.clone());
+            }
+        }
+    }
+}
+
+#[godot_api]
+impl Bumper {
+    /// Emitted after bouncing a character
+    #[signal]
+    fn bumped(player: Gd<Character>);
+    /// Emitted when a bump gives points, until `max_scoring_bumps` is reached
+    #[signal]
+    fn scored(player: Gd<Character>, points: i32);
+
+    #[func]
+    fn on_animation_finished(&mut self) {
+        if let Some(sprite) = &mut self.sprite {
+            sprite.play_ex().name("default").done();
+            sprite.stop();
+        }
+    }
+}
+
+impl Bumper {
+    fn bump(&mut self, mut player: Gd<Character>) {
+        let velocity = self.bounce_velocity(&player);
+        player.bind_mut().bump(velocity);
+        godot_print!("Bumper {velocity}");
+
+        if let Some(sprite) = &mut self.sprite {
+            sprite.play_ex().name("bump").done();
+        }
+        self.cooldowns.push((player.clone(), self.cooldown));
+        self.base_mut()
+            .emit_signal("bumped", &[player.to_variant()]);
+
+        if self.max_scoring_bumps < 0 || self.bumps < self.max_scoring_bumps {
+            self.bumps += 1;
+            let points = self.points;
+            self.base_mut()
+                .emit_signal("scored", &[player.to_variant(), points.to_variant()]);
+        }
+    }
+
+    fn bounce_velocity(&self, player: &Gd<Character>) -> Vector2 {
+        match self.shape {
+            BumperShape::Round => {
+                let delta = player.get_global_position() - self.base().get_global_position();
+                let angle = hex_angle(delta.y.atan2(delta.x));
+                Vector2::from_angle(angle) * self.bounce_speed
+            }
+            BumperShape::Triangle => {
+                // Keeps the speed along the face and replaces the speed away from it
+                let normal = Vector2::UP.rotated(self.base().get_global_rotation());
+                let velocity = player.bind().get_velocity();
+                velocity - normal * velocity.dot(normal) + normal * self.bounce_speed
+            }
+        }
+    }
+}
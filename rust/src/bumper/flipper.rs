@@ -0,0 +1,193 @@
+use godot::{classes::CollisionPolygon2D, prelude::*};
+
+use crate::{
+    character::{godot_api::State, Character},
+    object_manager::InteractiveObject,
+    solid_object::sloped_solid_object::SlopedSolidObject,
+};
+
+/// Casino Night Zone flipper (Sonic 2). The characters standing on the `sloped_solid_object` are put in pinball mode
+/// and can't jump, pressing jump flicks the flipper and launches them, faster the closer they are to the tip.
+/// The pivot is at the origin and the tip points right, or left with `facing_left`
+#[derive(GodotClass)]
+#[class(init, base=Node2D)]
+pub struct Flipper {
+    #[export]
+    sloped_solid_object: Option<Gd<SlopedSolidObject>>,
+    /// Collision polygon of the sloped solid object, its points are replaced when flicking
+    #[export]
+    collision_polygon: Option<Gd<CollisionPolygon2D>>,
+    /// Rotated around the pivot when flicking
+    #[export]
+    sprite: Option<Gd<Node2D>>,
+    #[export]
+    facing_left: bool,
+    #[export]
+    #[init(val = 48.0)]
+    length: f32,
+    #[export]
+    #[init(val = 8.0)]
+    thickness: f32,
+    /// How much lower the tip is than the pivot when resting, it is as much higher when flicked
+    #[export]
+    #[init(val = 16.0)]
+    drop: f32,
+    /// Launch speed at the pivot
+    #[export]
+    #[init(val = 8.0)]
+    min_launch_speed: f32,
+    /// Launch speed at the tip
+    #[export]
+    #[init(val = 14.0)]
+    max_launch_speed: f32,
+    /// Frames the flipper stays up after flicking
+    #[export]
+    #[init(val = 8)]
+    flick_frames: i32,
+
+    flick_timer: i32,
+    /// Characters put in pinball mode by the flipper
+    riders: Vec<Gd<Character>>,
+    /// True when registered with an `ObjectManager`, which checks the characters
+    managed: bool,
+    base: Base<Node2D>,
+}
+
+#[godot_api]
+impl INode2D for Flipper {
+    fn ready(&mut self) {
+        self.set_flicked(false);
+        self.register();
+    }
+    fn exit_tree(&mut self) {
+        // Characters on a flipper that goes away leave pinball mode
+        let source = self.base().clone().upcast::<Node>();
+        for mut player in std::mem::take(&mut self.riders) {
+            if player.is_instance_valid() {
+                set_pinball_mode(&mut player, &source, false);
+            }
+        }
+        self.unregister();
+    }
+    fn physics_process(&mut self, _delta: f64) {
+        self.process_unmanaged();
+    }
+}
+
+impl InteractiveObject for Flipper {
+    fn node(&self) -> Gd<Node2D> {
+        self.base().clone()
+    }
+    fn is_managed(&self) -> bool {
+        self.managed
+    }
+    fn set_managed(&mut self, value: bool) {
+        self.managed = value;
+    }
+    fn interact_players(&mut self, players: &[Gd<Character>]) {
+        if self.flick_timer > 0 {
+            self.flick_timer -= 1;
+            if self.flick_timer == 0 {
+                self.set_flicked(false);
+            }
+        }
+        self.update_riders(players);
+        let flick = self
+            .riders
+            .iter()
+            .any(|player| player.bind().input().jump_just_pressed);
+        if flick {
+            self.flick();
+        }
+    }
+}
+
+#[godot_api]
+impl Flipper {
+    /// Emitted for each character launched by the flipper
+    #[signal]
+    fn launched(player: Gd<Character>);
+}
+
+impl Flipper {
+    const fn side(&self) -> f32 {
+        if self.facing_left {
+            -1.0
+        } else {
+            1.0
+        }
+    }
+
+    /// Puts the characters that started standing on the flipper in pinball mode,
+    /// and lets go of the ones that left
+    fn update_riders(&mut self, players: &[Gd<Character>]) {
+        let Some(object) = self.sloped_solid_object.clone() else {
+            return;
+        };
+        let object = object.upcast::<Node2D>();
+        let source = self.base().clone().upcast::<Node>();
+        let standing: Vec<Gd<Character>> = players
+            .iter()
+            .filter(|player| player.bind().get_standing_object() == Some(object.clone()))
+            .cloned()
+            .collect();
+        for player in &mut self.riders {
+            if player.is_instance_valid() && !standing.contains(player) {
+                set_pinball_mode(player, &source, false);
+            }
+        }
+        for player in &standing {
+            if !self.riders.contains(player) {
+                set_pinball_mode(&mut player.clone(), &source, true);
+            }
+        }
+        self.riders = standing;
+    }
+
+    fn flick(&mut self) {
+        godot_print!("Flipper flick");
+        self.set_flicked(true);
+        self.flick_timer = self.flick_frames;
+        let pivot = self.base().get_global_position().x;
+        let side = self.side();
+        let source = self.base().clone().upcast::<Node>();
+        for mut player in std::mem::take(&mut self.riders) {
+            // 0 at the pivot and 1 at the tip
+            let t = ((player.get_global_position().x - pivot) * side / self.length).clamp(0.0, 1.0);
+            let speed = self.min_launch_speed + (self.max_launch_speed - self.min_launch_speed) * t;
+            // Characters near the pivot are also sent back over it
+            let velocity = Vector2::new(-side * (1.0 - t) * speed * 0.5, -speed);
+            set_pinball_mode(&mut player, &source, false);
+            player.bind_mut().bump(velocity);
+            self.base_mut()
+                .emit_signal("launched", &[player.to_variant()]);
+        }
+    }
+
+    fn set_flicked(&mut self, flicked: bool) {
+        let (side, length, thickness) = (self.side(), self.length, self.thickness);
+        let tip = if flicked { -self.drop } else { self.drop };
+        let polygon = PackedVector2Array::from(&[
+            Vector2::ZERO,
+            Vector2::new(side * length, tip),
+            Vector2::new(side * length, tip + thickness),
+            Vector2::new(0.0, thickness),
+        ]);
+        if let Some(collision_polygon) = &mut self.collision_polygon {
+            collision_polygon.set_polygon(&polygon);
+        }
+        if let Some(sprite) = &mut self.sprite {
+            sprite.set_rotation((tip / length).atan() * side);
+        }
+    }
+}
+
+/// Riders roll without being able to unroll or jump, the flipper does the jumping
+fn set_pinball_mode(player: &mut Gd<Character>, source: &Gd<Node>, value: bool) {
+    let mut player = player.bind_mut();
+    player.set_force_roll(source.clone(), value);
+    player.set_jump_locked(source.clone(), value);
+    if value {
+        player.set_state(State::RollingBall);
+    }
+}
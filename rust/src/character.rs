@@ -1,13 +1,17 @@
-mod airborne;
+pub mod airborne;
 mod collision;
 pub mod godot_api;
 mod grounded;
+pub mod input;
 mod lifecycle;
 mod utils;
 
+use std::collections::{HashMap, HashSet};
+
 use godot::classes::{AnimatedSprite2D, CollisionShape2D};
 use godot::prelude::*;
 use godot_api::{SolidObjectKind, State};
+use input::CharacterInput;
 
 #[derive(GodotConvert, Var, Export, Default, Debug, PartialEq, Eq, Clone, Copy)]
 #[godot(via = GString)]
@@ -68,7 +72,7 @@ enum SpindashGenesisState {
     },
 }
 
-use crate::sensor::Sensor;
+use crate::sensor::{Sensor, SurfaceProperties};
 /// Player class, the code is from all over <https://info.sonicretro.org/Sonic_Physics_Guide>
 /// but I will point to specifics when needed
 #[allow(clippy::struct_excessive_bools)]
@@ -158,6 +162,17 @@ pub struct Character {
     /// Used to stop accepting input for a time
     #[var(set, get)]
     control_lock_timer: i32,
+    /// Objects keeping the character in pinball mode with the ground speed each of them gives, see `set_force_roll`
+    force_roll_sources: HashMap<InstanceId, f32>,
+    /// Ground speed given in pinball mode when the character is about to stop rolling,
+    /// for the objects that don't choose their own
+    #[var(get, set)]
+    #[init(val = 4.0)]
+    force_roll_speed: f32,
+    /// Objects stopping the character from jumping, see `set_jump_locked`
+    jump_lock_sources: HashSet<InstanceId>,
+    /// Objects moving the character along their own path, see `set_object_control`
+    object_control_sources: HashSet<InstanceId>,
     /// Set to true to display the sensors and ground angle
     #[export]
     debug_draw: bool,
@@ -207,6 +222,8 @@ pub struct Character {
     regather_rings_timer: i32,
     #[var(set, get)]
     spring_bounce_timer: i32,
+    /// Plays the twirl animation during the spring bounce
+    spring_twirl: bool,
 
     #[init(val = 2.0)]
     hurt_x_force: f32,
@@ -215,11 +232,42 @@ pub struct Character {
     #[init(val = 0.1875)]
     hurt_gravity: f32,
 
+    /// Set to true when the character is driven by a CPU controller (like a `Sidekick`) instead of the keyboard
+    #[export]
+    #[var(get, set)]
+    cpu_controlled: bool,
+    /// Prefix added to the input actions, for example `p2_` for the second player
+    #[export]
+    input_prefix: GString,
+    /// Input used for the current frame
+    input: CharacterInput,
+    /// Set to false to make solid objects and layer switchers ignore the character
+    #[export]
+    #[var(get, set)]
+    #[init(val = true)]
+    object_collision: bool,
+    /// Set to false to stop the scene from reloading when the character dies, `died` is emitted either way
+    #[export]
+    #[var(get, set)]
+    #[init(val = true)]
+    reload_on_death: bool,
+    /// True when the `ObjectManager` steps the character as part of its frame pipeline,
+    /// instead of the character moving in its own physics process
+    scheduled: bool,
+
     /// Set to true to make the delta used for the player fixed to 60 FPS
     #[export]
     #[init(val = true)]
     fix_delta: bool,
 
     solid_object_to_stand_on: Option<SolidObjectKind>,
+    /// Properties of the solid tile the character is standing on
+    surface: SurfaceProperties,
+    /// Object the character is pushing
+    pushed_object: Option<Gd<Node2D>>,
+    /// 1 when pushing right, -1 when pushing left
+    push_direction: i32,
+    /// True when the pushed object collided with the character this frame
+    push_contact: bool,
     base: Base<Node2D>,
 }
@@ -1,23 +1,96 @@
 use crate::character::utils::MotionDirection;
 
-use super::{godot_api::State, Character, DropDashState, MidAirAction};
+use super::{godot_api::State, input::CharacterInput, Character, DropDashState, MidAirAction};
+use crate::sensor::{DetectionResult, Solidity};
 use godot::prelude::*;
 
+/// Airborne motion without input, shared by the characters and the trajectory previews of the objects
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AirPhysics {
+    pub gravity: f32,
+    pub air_acceleration: f32,
+    pub top_speed: f32,
+}
+
+impl Default for AirPhysics {
+    /// Same as the default `Character`
+    fn default() -> Self {
+        Self {
+            gravity: 0.21875,
+            air_acceleration: 0.09375,
+            top_speed: 6.0,
+        }
+    }
+}
+
+impl AirPhysics {
+    /// From <https://info.sonicretro.org/SPG:Air_State>
+    /// `direction` is the held horizontal input, -1 for left, 1 for right and 0 for none
+    pub fn accelerate(self, mut velocity: Vector2, direction: f32, delta: f32) -> Vector2 {
+        if direction < 0.0 {
+            velocity.x -= self.air_acceleration * delta;
+            velocity.x = velocity.x.max(-self.top_speed);
+        } else if direction > 0.0 {
+            velocity.x += self.air_acceleration * delta;
+            velocity.x = velocity.x.min(self.top_speed);
+        }
+        velocity
+    }
+    /// From <https://info.sonicretro.org/SPG:Air_State#Air_Drag>
+    pub fn drag(mut velocity: Vector2, delta: f32) -> Vector2 {
+        if velocity.y < 0.0 && velocity.y > -4.0 {
+            velocity.x -= (velocity.x.div_euclid(0.125)) / 256.0 * delta;
+        }
+        velocity
+    }
+    /// From <https://info.sonicretro.org/SPG:Air_State#Gravity>
+    pub fn fall(self, mut velocity: Vector2, delta: f32) -> Vector2 {
+        velocity.y += self.gravity * delta;
+        // Top y speed
+        velocity.y = velocity.y.min(16.0);
+        velocity
+    }
+    /// One 60 Hz frame of airborne motion, in the same order as `Character::airborne`
+    pub fn step(self, position: Vector2, velocity: Vector2, direction: f32) -> (Vector2, Vector2) {
+        let velocity = self.accelerate(velocity, direction, 1.0);
+        let velocity = Self::drag(velocity, 1.0);
+        let position = position + velocity;
+        (position, self.fall(velocity, 1.0))
+    }
+    /// Positions of the character for the next `frames` frames after being launched with `velocity`,
+    /// holding the input `direction` the whole time
+    pub fn trajectory(
+        self,
+        position: Vector2,
+        velocity: Vector2,
+        direction: f32,
+        frames: i32,
+    ) -> Vec<Vector2> {
+        let mut points = vec![position];
+        let (mut position, mut velocity) = (position, velocity);
+        for _ in 0..frames {
+            (position, velocity) = self.step(position, velocity, direction);
+            points.push(position);
+        }
+        points
+    }
+}
+
 impl Character {
     pub(super) fn airborne(&mut self, delta: f32) {
         // Airborne
-        let input = Input::singleton();
+        let input = self.input;
 
         godot_print!("Airborne");
 
-        self.handle_mid_air_action(&input);
+        self.handle_mid_air_action(input);
 
         // Disable input when hurt
         let can_input = !self.state.is_hurt();
         if can_input {
-            self.handle_variable_jump(&input);
+            self.handle_variable_jump(input);
 
-            self.air_accelerate(&input, delta);
+            self.air_accelerate(input, delta);
 
             self.air_drag(delta);
         }
@@ -37,12 +110,12 @@ impl Character {
         self.check_floor_air();
     }
 
-    fn handle_mid_air_action(&mut self, input: &Gd<Input>) {
+    fn handle_mid_air_action(&mut self, input: CharacterInput) {
         match self.mid_air_action {
             // From <https://info.sonicretro.org/SPG:Special_Abilities#Drop_Dash_.28Mania.29>
             MidAirAction::DropDash => {
                 if self.state == State::JumpBall {
-                    let is_jump_pressed = input.is_action_pressed(c"jump");
+                    let is_jump_pressed = input.jump;
                     if !is_jump_pressed {
                         self.has_released_jump = true;
                     }
@@ -76,7 +149,7 @@ impl Character {
                     }
                 }
                 if self.state == State::JumpBall {
-                    let is_jump_pressed = input.is_action_just_pressed(c"jump");
+                    let is_jump_pressed = input.jump_just_pressed;
                     if is_jump_pressed {
                         self.insta_shield_timer = 14;
                         // Set bigger attacking hitbox
@@ -98,8 +171,8 @@ impl Character {
         }
     }
     /// From <https://info.sonicretro.org/SPG:Jumping#Variable_Jump_Height>
-    fn handle_variable_jump(&mut self, input: &Gd<Input>) {
-        if self.has_jumped && !input.is_action_pressed(c"jump") && self.velocity.y < -4.0 {
+    fn handle_variable_jump(&mut self, input: CharacterInput) {
+        if self.has_jumped && !input.jump && self.velocity.y < -4.0 {
             self.velocity.y = -4.0;
         }
     }
@@ -114,8 +187,16 @@ impl Character {
                         position.y += result.distance;
                         self.set_global_position(position);
 
+                        if result.surface.bounce > 0.0 {
+                            godot_print!("Bounce on surface");
+                            self.velocity.y = -result.surface.bounce;
+                            self.has_jumped = false;
+                            return;
+                        }
+
                         godot_print!("floor collision dy:{}", result.distance);
                         self.set_ground_angle_from_result(result);
+                        self.set_surface(result.surface);
                         self.set_grounded(true);
                         self.has_jumped = false;
                         self.land_on_floor();
@@ -246,40 +327,139 @@ impl Character {
         }
     }
 
-    /// From <https://info.sonicretro.org/SPG:Air_State#Gravity>
     fn apply_gravity(&mut self, delta: f32) {
         godot_print!("Apply gravity");
-        if self.state.is_hurt() {
-            self.velocity.y += self.hurt_gravity * delta;
+        self.velocity = self.air_physics().fall(self.velocity, delta);
+    }
+
+    /// Airborne tunables of the character, with the hurt gravity while hurt
+    pub(super) const fn air_physics(&self) -> AirPhysics {
+        let gravity = if self.state.is_hurt() {
+            self.hurt_gravity
         } else {
-            self.velocity.y += self.gravity * delta;
+            self.gravity
+        };
+        AirPhysics {
+            gravity,
+            air_acceleration: self.air_acceleration,
+            top_speed: self.top_speed,
+        }
+    }
+
+    /// Simulates `frames` frames of airborne motion from the current position, returning the global positions.
+    /// With `collide` the sensors are probed along the way: walls and ceilings stop the motion on that axis
+    /// and the path ends when landing on a floor
+    pub(super) fn simulate_trajectory(
+        &self,
+        velocity: Vector2,
+        frames: i32,
+        direction: f32,
+        collide: bool,
+    ) -> Vec<Vector2> {
+        let physics = self.air_physics();
+        let origin = self.global_position();
+        let mut points = vec![origin];
+        let (mut position, mut velocity) = (origin, velocity);
+        for _ in 0..frames {
+            (position, velocity) = physics.step(position, velocity, direction);
+            let landed = collide && self.predict_collisions(origin, &mut position, &mut velocity);
+            points.push(position);
+            if landed {
+                break;
+            }
+        }
+        points
+    }
+
+    /// Moves the predicted `position` out of the walls, ceilings and floors detected by the sensors,
+    /// probing with the velocity applied like `airborne` does. Returns true when landing
+    fn predict_collisions(
+        &self,
+        origin: Vector2,
+        position: &mut Vector2,
+        velocity: &mut Vector2,
+    ) -> bool {
+        let offset = *position - origin + *velocity;
+        let fully = |r: &DetectionResult| r.solidity == Solidity::Fully;
+        if velocity.x >= 0.0 {
+            let sensor = self.sensor_push_right.clone();
+            if let Some(result) = Self::sensor_results_at(&mut [sensor], offset)
+                .into_iter()
+                .find(fully)
+            {
+                if result.distance < 0.0 {
+                    position.x += result.distance;
+                    velocity.x = 0.0;
+                }
+            }
+        }
+        if velocity.x <= 0.0 {
+            let sensor = self.sensor_push_left.clone();
+            if let Some(result) = Self::sensor_results_at(&mut [sensor], offset)
+                .into_iter()
+                .find(fully)
+            {
+                if result.distance < 0.0 {
+                    position.x -= result.distance;
+                    velocity.x = 0.0;
+                }
+            }
+        }
+        let closest = |results: Vec<DetectionResult>| {
+            results
+                .into_iter()
+                .min_by(|a, b| a.distance.total_cmp(&b.distance))
+        };
+        if velocity.y < 0.0 {
+            let mut sensors = [
+                self.sensor_ceiling_left.clone(),
+                self.sensor_ceiling_right.clone(),
+            ];
+            let results = Self::sensor_results_at(&mut sensors, offset);
+            if let Some(result) = closest(results.into_iter().filter(fully).collect()) {
+                if result.distance < 0.0 {
+                    position.y -= result.distance;
+                    velocity.y = 0.0;
+                }
+            }
+            return false;
+        }
+        let mut sensors = [
+            self.sensor_floor_left.clone(),
+            self.sensor_floor_right.clone(),
+        ];
+        match closest(Self::sensor_results_at(&mut sensors, offset)) {
+            Some(result) if result.distance < 0.0 => {
+                position.y += result.distance;
+                true
+            }
+            _ => false,
         }
-        // Top y speed
-        self.velocity.y = self.velocity.y.min(16.0);
     }
 
     /// From <https://info.sonicretro.org/SPG:Air_State>
-    fn air_accelerate(&mut self, input: &Gd<Input>, delta: f32) {
-        if input.is_action_pressed(c"left") {
+    fn air_accelerate(&mut self, input: CharacterInput, delta: f32) {
+        let direction = input.horizontal();
+        if direction < 0 {
             godot_print!("Accelerate left");
-            self.velocity.x -= self.air_acceleration * delta;
             self.set_flip_h(true);
-            self.velocity.x = self.velocity.x.max(-self.top_speed);
-        }
-        if input.is_action_pressed(c"right") {
+        } else if direction > 0 {
             godot_print!("Accelerate right");
-            self.velocity.x += self.air_acceleration * delta;
             self.set_flip_h(false);
-            self.velocity.x = self.velocity.x.min(self.top_speed);
         }
+        #[allow(clippy::cast_precision_loss)]
+        let direction = direction as f32;
+        self.velocity = self
+            .air_physics()
+            .accelerate(self.velocity, direction, delta);
     }
 
-    /// From <https://info.sonicretro.org/SPG:Air_State#Air_Drag>
     fn air_drag(&mut self, delta: f32) {
-        if self.velocity.y < 0.0 && self.velocity.y > -4.0 {
+        let velocity = AirPhysics::drag(self.velocity, delta);
+        if velocity != self.velocity {
             godot_print!("Apply drag");
-            self.velocity.x -= (self.velocity.x.div_euclid(0.125)) / 256.0 * delta;
         }
+        self.velocity = velocity;
     }
     /// From <https://info.sonicretro.org/SPG:Slope_Physics#When_Falling_Downward>
     fn land_on_floor(&mut self) {
@@ -336,3 +516,58 @@ impl Character {
         self.set_velocity(Vector2::ZERO);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use godot::builtin::math::assert_eq_approx;
+    use godot::prelude::*;
+
+    use super::AirPhysics;
+
+    #[test]
+    fn accelerate_clamps_to_top_speed() {
+        let physics = AirPhysics::default();
+        let velocity = physics.accelerate(Vector2::new(5.95, 0.0), 1.0, 1.0);
+        assert_eq_approx!(velocity.x, 6.0);
+        let velocity = physics.accelerate(Vector2::new(-5.95, 0.0), -1.0, 1.0);
+        assert_eq_approx!(velocity.x, -6.0);
+        let velocity = physics.accelerate(Vector2::new(3.0, 0.0), 0.0, 1.0);
+        assert_eq_approx!(velocity.x, 3.0);
+    }
+
+    #[test]
+    fn drag_only_near_the_top_of_a_jump() {
+        let rising = AirPhysics::drag(Vector2::new(4.0, -2.0), 1.0);
+        assert_eq_approx!(rising.x, 4.0 - 32.0 / 256.0);
+        let fast_rising = AirPhysics::drag(Vector2::new(4.0, -5.0), 1.0);
+        assert_eq_approx!(fast_rising.x, 4.0);
+        let falling = AirPhysics::drag(Vector2::new(4.0, 2.0), 1.0);
+        assert_eq_approx!(falling.x, 4.0);
+    }
+
+    #[test]
+    fn fall_clamps_to_top_y_speed() {
+        let physics = AirPhysics::default();
+        assert_eq_approx!(physics.fall(Vector2::ZERO, 1.0).y, 0.21875);
+        assert_eq_approx!(physics.fall(Vector2::new(0.0, 15.9), 1.0).y, 16.0);
+    }
+
+    #[test]
+    fn step_moves_before_gravity() {
+        let physics = AirPhysics::default();
+        let (position, velocity) = physics.step(Vector2::ZERO, Vector2::new(1.0, -6.5), 0.0);
+        assert_eq_approx!(position, Vector2::new(1.0, -6.5));
+        assert_eq_approx!(velocity, Vector2::new(1.0, -6.5 + 0.21875));
+    }
+
+    #[test]
+    fn trajectory_has_the_start_and_each_frame() {
+        let physics = AirPhysics::default();
+        let points = physics.trajectory(Vector2::ZERO, Vector2::new(0.0, -4.0), 1.0, 10);
+        assert_eq!(points.len(), 11);
+        assert_eq_approx!(points[0], Vector2::ZERO);
+        let (position, velocity) = physics.step(Vector2::ZERO, Vector2::new(0.0, -4.0), 1.0);
+        assert_eq_approx!(points[1], position);
+        assert_eq_approx!(points[2], physics.step(position, velocity, 1.0).0);
+    }
+}
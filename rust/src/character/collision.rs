@@ -52,20 +52,27 @@ impl Character {
         &self,
         sensors: &mut [Option<Gd<Sensor>>],
         apply_velocity: bool,
+    ) -> Vec<DetectionResult> {
+        let offset = if apply_velocity {
+            self.velocity
+        } else {
+            Vector2::ZERO
+        };
+        Self::sensor_results_at(sensors, offset)
+    }
+    /// Senses with the sensors moved by `offset`, putting them back afterwards
+    pub(super) fn sensor_results_at(
+        sensors: &mut [Option<Gd<Sensor>>],
+        offset: Vector2,
     ) -> Vec<DetectionResult> {
         let mut results = vec![];
         for sensor in sensors.iter_mut().flatten() {
             let position = sensor.get_position();
-            if apply_velocity {
-                let next_position = position + self.velocity;
-                sensor.set_position(next_position);
-            }
+            sensor.set_position(position + offset);
             if let Some(r) = sensor.bind_mut().sense() {
                 results.push(r);
             }
-            if apply_velocity {
-                sensor.set_position(position);
-            }
+            sensor.set_position(position);
         }
         results
     }
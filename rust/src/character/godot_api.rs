@@ -35,7 +35,7 @@ impl State {
     ///
     /// [`RollingBall`]: State::RollingBall
     #[must_use]
-    pub(super) const fn is_rolling(self) -> bool {
+    pub const fn is_rolling(self) -> bool {
         matches!(self, Self::RollingBall)
     }
 
@@ -112,14 +112,37 @@ impl State {
     }
 }
 use crate::{
-    character::{Character, SpindashStyle},
+    character::{input::CharacterInput, Character, SpindashStyle},
     sensor::DetectionResult,
-    solid_object::{sloped_solid_object::SlopedSolidObject, SolidObject},
+    solid_object::{
+        hazard_object::HazardObject, pushable_block::PushableBlock,
+        sloped_solid_object::SlopedSolidObject, SolidBody, SolidObject,
+    },
 };
 
+/// Ground speed set while pushing, so that the pushing animation plays at the right speed
+const PUSH_GROUND_SPEED: f32 = 0.25;
+
 pub enum SolidObjectKind {
     Simple(Gd<SolidObject>),
     Sloped(Gd<SlopedSolidObject>),
+    Pushable(Gd<PushableBlock>),
+    Hazard(Gd<HazardObject>),
+}
+
+impl SolidObjectKind {
+    /// Calls `f` with the object as a `SolidBody`
+    pub fn with_body<R>(&self, f: impl FnOnce(&dyn SolidBody) -> R) -> R {
+        match self {
+            Self::Simple(object) => f(&*object.bind()),
+            Self::Sloped(object) => f(&*object.bind()),
+            Self::Pushable(object) => f(&*object.bind()),
+            Self::Hazard(object) => f(&*object.bind()),
+        }
+    }
+    pub fn node(&self) -> Gd<Node2D> {
+        self.with_body(|body| body.node().upcast())
+    }
 }
 
 #[godot_api]
@@ -159,6 +182,12 @@ impl Character {
     }
     #[signal]
     fn rings_changed(value: i32);
+    #[signal]
+    fn died();
+    #[signal]
+    fn push_started(object: Gd<Node2D>);
+    #[signal]
+    fn push_ended(object: Gd<Node2D>);
     #[func]
     pub(super) fn reset_idle_from_skidding(&mut self) {
         if self.state == State::Skidding {
@@ -166,7 +195,7 @@ impl Character {
         }
     }
     #[func]
-    pub(super) fn set_rings(&mut self, value: i32) {
+    pub fn set_rings(&mut self, value: i32) {
         self.rings = value;
         self.base_mut()
             .emit_signal("rings_changed", &[Variant::from(value)]);
@@ -189,6 +218,196 @@ impl Character {
         self.land();
         self.has_jumped = false;
     }
+    /// Launches the character upwards like a vertical spring, with the spring bounce animation
+    #[func]
+    pub fn launch_upwards(&mut self, speed: f32) {
+        let velocity = Vector2::new(self.velocity.x, -speed);
+        self.spring_bounce(velocity, false);
+    }
+    /// From <https://info.sonicretro.org/SPG:Game_Objects#Springs>
+    /// Launches the character into the air with the spring bounce animation for 48 frames,
+    /// `twirl` plays the `spring_twirl` animation instead when the sprites have it
+    #[func]
+    pub fn spring_bounce(&mut self, velocity: Vector2, twirl: bool) {
+        self.clear_standing_objects();
+        self.has_jumped = false;
+        self.spring_twirl = twirl;
+        self.set_state(State::SpringBounce);
+        self.spring_bounce_timer = 48;
+        self.velocity = velocity;
+        if velocity.x != 0.0 {
+            self.set_flip_h(velocity.x < 0.0);
+        }
+    }
+    /// Global positions of the character for the next `frames` frames if it were launched with `initial_velocity`,
+    /// using its gravity, air drag, air acceleration and top speed.
+    /// `input_direction` is the held horizontal input (-1, 0 or 1), with `collide` the path stops at walls and ceilings
+    /// and ends when landing on a floor detected by the sensors
+    #[func]
+    pub fn predict_trajectory(
+        &self,
+        initial_velocity: Vector2,
+        frames: i32,
+        input_direction: f32,
+        collide: bool,
+    ) -> PackedVector2Array {
+        let points = self.simulate_trajectory(initial_velocity, frames, input_direction, collide);
+        PackedVector2Array::from(points.as_slice())
+    }
+    /// From <https://info.sonicretro.org/SPG:Game_Objects#Horizontal_Springs>
+    /// Sets the speed of the character, facing the new direction and locking the controls for 16 frames
+    #[func]
+    pub fn spring_sideways(&mut self, speed: f32) {
+        if self.is_grounded {
+            self.ground_speed = speed;
+        } else {
+            self.velocity.x = speed;
+            self.velocity.y = 0.0;
+        }
+        self.set_flip_h(speed < 0.0);
+        self.control_lock_timer = 16;
+    }
+    /// Pinball mode, the character keeps rolling instead of unrolling when slowing down
+    #[func]
+    pub fn get_force_roll(&self) -> bool {
+        !self.force_roll_sources.is_empty()
+    }
+    /// Puts the character in pinball mode on behalf of `source`, or releases it.
+    /// It stays in pinball mode until every source released it, so overlapping zones don't cancel each other
+    #[func]
+    pub fn set_force_roll(&mut self, source: Gd<Node>, value: bool) {
+        if value {
+            self.set_force_roll_with_speed(source, self.force_roll_speed);
+        } else {
+            self.force_roll_sources.remove(&source.instance_id());
+        }
+    }
+    /// Puts the character in pinball mode on behalf of `source`, which pushes it at `speed` when it's about to stop.
+    /// With overlapping sources the fastest push wins
+    #[func]
+    pub fn set_force_roll_with_speed(&mut self, source: Gd<Node>, speed: f32) {
+        self.force_roll_sources.insert(source.instance_id(), speed);
+    }
+    /// Stops the character from jumping, used by objects that take the jump button for themselves like flippers
+    #[func]
+    pub fn get_jump_locked(&self) -> bool {
+        !self.jump_lock_sources.is_empty()
+    }
+    /// Locks jumping on behalf of `source`, or releases it. Jumping stays locked until every source released it
+    #[func]
+    pub fn set_jump_locked(&mut self, source: Gd<Node>, value: bool) {
+        if value {
+            self.jump_lock_sources.insert(source.instance_id());
+        } else {
+            self.jump_lock_sources.remove(&source.instance_id());
+        }
+    }
+    /// Set by objects that move the character along their own path like corkscrews,
+    /// the character stays grounded without checking the terrain
+    #[func]
+    pub fn get_object_control(&self) -> bool {
+        !self.object_control_sources.is_empty()
+    }
+    /// Takes control of the character on behalf of `source`, or releases it.
+    /// It stays under object control until every source released it, so two corkscrews don't cancel each other
+    #[func]
+    pub fn set_object_control(&mut self, source: Gd<Node>, value: bool) {
+        if value {
+            self.object_control_sources.insert(source.instance_id());
+        } else {
+            self.object_control_sources.remove(&source.instance_id());
+        }
+    }
+    /// From <https://info.sonicretro.org/SPG:Game_Objects#Bumpers>
+    /// Knocks the character into the air with `velocity`, it can't control the height like after a jump
+    #[func]
+    pub fn bump(&mut self, velocity: Vector2) {
+        self.clear_standing_objects();
+        self.end_pushing();
+        self.has_jumped = false;
+        self.velocity = velocity;
+    }
+    /// Object the character is standing on, if any
+    #[func]
+    pub fn get_standing_object(&self) -> Option<Gd<Node2D>> {
+        self.solid_object_to_stand_on
+            .as_ref()
+            .map(SolidObjectKind::node)
+    }
+    #[func]
+    pub fn set_stand_on_hazard_object(&mut self, object: Gd<HazardObject>) {
+        self.solid_object_to_stand_on = Some(SolidObjectKind::Hazard(object));
+        self.land();
+        self.has_jumped = false;
+    }
+    #[func]
+    pub fn set_stand_on_pushable_block(&mut self, object: Gd<PushableBlock>) {
+        self.solid_object_to_stand_on = Some(SolidObjectKind::Pushable(object));
+        self.land();
+        self.has_jumped = false;
+    }
+    /// Called by a solid object when the character collides with one of its sides.
+    /// From <https://info.sonicretro.org/SPG:Solid_Objects#Pushing>, the character only pushes
+    /// when grounded and walking towards the object. `direction` is 1 when pushing right and -1 when pushing left
+    #[func]
+    pub fn start_pushing(&mut self, object: Gd<Node2D>, direction: i32) {
+        if !self.is_grounded || self.input.horizontal() != direction {
+            return;
+        }
+        if self.pushed_object.as_ref() == Some(&object) {
+            self.push_contact = true;
+            return;
+        }
+        if self.state.is_pushing() {
+            self.set_state(State::Idle);
+        }
+        self.pushed_object = Some(object.clone());
+        self.push_direction = direction;
+        self.push_contact = true;
+        self.set_state(State::Pushing);
+        godot_print!("Start pushing");
+        self.base_mut()
+            .emit_signal("push_started", &[object.to_variant()]);
+    }
+    /// Stops pushing, for example when the pushed object slides off a ledge
+    #[func]
+    pub fn end_pushing(&mut self) {
+        if self.state.is_pushing() {
+            self.set_state(State::Idle);
+        }
+    }
+    #[func]
+    pub fn get_pushed_object(&self) -> Option<Gd<Node2D>> {
+        self.pushed_object.clone()
+    }
+    /// 1 when pushing right, -1 when pushing left, 0 when not pushing
+    #[func]
+    #[allow(clippy::missing_const_for_fn)]
+    fn get_push_direction(&self) -> i32 {
+        self.push_direction
+    }
+    /// Ground speed of the character while pushing, only used for the animation speed
+    #[func]
+    #[allow(clippy::missing_const_for_fn, clippy::cast_precision_loss)]
+    fn get_push_speed(&self) -> f32 {
+        PUSH_GROUND_SPEED * self.push_direction as f32
+    }
+    /// From <https://info.sonicretro.org/SPG:Game_Objects#Pushable_Blocks>
+    /// Moves the character together with the pushed object, which already moved by `distance`:
+    /// the character is moved back by 1 pixel out of the object and then forward by `distance`
+    #[func]
+    pub fn push_object(&mut self, distance: f32) {
+        if self.pushed_object.is_none() {
+            return;
+        }
+        #[allow(clippy::cast_precision_loss)]
+        let direction = self.push_direction as f32;
+        let mut position = self.global_position();
+        position.x += (distance - 1.0) * direction;
+        self.set_global_position(position);
+        self.velocity.x = 0.0;
+        self.ground_speed = PUSH_GROUND_SPEED * direction;
+    }
     /// From <https://info.sonicretro.org/SPG:Rebound>
     #[func]
     fn on_attacking(&mut self, badnik: Gd<Node2D>, is_boss: bool) {
@@ -210,22 +429,34 @@ impl Character {
     /// From <https://info.sonicretro.org/SPG:Getting_Hit>
     #[func]
     fn on_hurt(&mut self, hazard: Gd<Node2D>) {
+        self.hurt_from(hazard.get_global_position().x);
+    }
+    /// The character is knocked away from `hazard_x`, returns false if it was invulnerable
+    pub fn hurt_from(&mut self, hazard_x: f32) -> bool {
         if self.is_invulnerable() {
-            return;
+            return false;
         }
         if self.rings <= 0 {
             // Death
             self.die();
-            return;
+            return true;
         }
         self.regather_rings_timer = 64;
         self.scatter_rings();
-        let hazard_position = hazard.get_global_position();
-        let sign = (self.global_position().x - hazard_position.x).signum();
+        let sign = (self.global_position().x - hazard_x).signum();
         self.velocity = Vector2::new(self.hurt_x_force * sign, self.hurt_y_force);
         self.set_state(State::Hurt);
         self.set_grounded(false);
         self.clear_standing_objects();
+        true
+    }
+    /// Like `hurt_from`, but ignores the invulnerability after getting hit (Sonic 1 spikes)
+    pub fn hurt_ignoring_invulnerability(&mut self, hazard_x: f32) -> bool {
+        if self.state.is_hurt() {
+            return false;
+        }
+        self.invulnerability_timer = 0;
+        self.hurt_from(hazard_x)
     }
     #[func]
     #[allow(clippy::missing_const_for_fn)]
@@ -275,10 +506,58 @@ impl Character {
     }
 
     pub fn die(&self) {
+        self.base().clone().emit_signal("died", &[]);
+        if !self.reload_on_death {
+            return;
+        }
         if let Some(mut tree) = self.base().get_tree() {
             tree.call_deferred("reload_current_scene", &[]);
         }
     }
+    fn stop_pushing(&mut self) {
+        self.push_direction = 0;
+        self.push_contact = false;
+        if let Some(object) = self.pushed_object.take() {
+            godot_print!("End pushing");
+            self.base_mut()
+                .emit_signal("push_ended", &[object.to_variant()]);
+        }
+    }
+    /// Ends pushing when the pushed object didn't touch the character during the last frame
+    pub(super) fn check_push_contact(&mut self) {
+        if self.pushed_object.is_some() && !self.push_contact {
+            self.end_pushing();
+        }
+        self.push_contact = false;
+    }
+    /// Input used by the character this frame
+    pub(crate) const fn input(&self) -> CharacterInput {
+        self.input
+    }
+    /// Input for the next frame when the character is `cpu_controlled`
+    pub(crate) const fn set_cpu_input(&mut self, input: CharacterInput) {
+        self.input = input;
+    }
+    pub(crate) const fn set_scheduled(&mut self, value: bool) {
+        self.scheduled = value;
+    }
+    /// All the characters in the scene tree
+    pub fn characters_in_tree(node: &Gd<Node>) -> Vec<Gd<Self>> {
+        let Some(mut tree) = node.get_tree() else {
+            return vec![];
+        };
+        tree.get_nodes_in_group("player")
+            .iter_shared()
+            .filter_map(|node| node.try_cast::<Self>().ok())
+            .collect()
+    }
+    /// All the characters in the scene tree that objects should collide with
+    pub fn players_in_tree(node: &Gd<Node>) -> Vec<Gd<Self>> {
+        Self::characters_in_tree(node)
+            .into_iter()
+            .filter(|player| player.bind().object_collision)
+            .collect()
+    }
     #[func]
     pub fn set_collision_layer(&mut self, value: u32) {
         self.collision_layer = value;
@@ -358,6 +637,9 @@ impl Character {
         if self.state.is_hurt() && !value.is_hurt() {
             self.invulnerability_timer = 120;
         }
+        if self.state.is_pushing() && !value.is_pushing() {
+            self.stop_pushing();
+        }
         self.state = value;
         let is_editor = Engine::singleton().is_editor_hint();
         if was_ball && !is_ball {
@@ -381,7 +663,13 @@ impl Character {
             State::Hurt => self.play_animation("hurt"),
             State::Skidding => self.play_animation("skidding"),
             State::Pushing => self.play_animation("pushing"),
-            State::SpringBounce => self.play_animation("spring_bounce"),
+            State::SpringBounce => {
+                if self.spring_twirl && self.has_animation("spring_twirl") {
+                    self.play_animation("spring_twirl");
+                } else {
+                    self.play_animation("spring_bounce");
+                }
+            }
             State::Crouch => self.play_animation("crouch"),
             State::SuperPeelOut => self.play_animation("super_peel_out"),
             State::LookUp => self.play_animation("look_up"),
@@ -1,51 +1,72 @@
 use real_consts::PI;
 
-use crate::character::{godot_api::State, utils::Mode};
+use crate::{
+    character::{godot_api::State, utils::Mode},
+    sensor::SurfaceProperties,
+};
 
 use super::{
-    utils::inverse_lerp, Character, SpindashCDState, SpindashGenesisState, SpindashStyle,
-    SuperPeeloutState,
+    input::CharacterInput, utils::inverse_lerp, Character, SpindashCDState, SpindashGenesisState,
+    SpindashStyle, SuperPeeloutState,
 };
 use godot::prelude::*;
 
 impl Character {
     pub(super) fn grounded(&mut self, delta: f32) {
         // Grounded
-        let input = Input::singleton();
+        let input = self.input;
 
         godot_print!("Grounded");
         self.check_unrolling();
 
-        self.handle_spindash(&input, delta);
+        self.handle_spindash(input, delta);
 
-        self.handle_super_peel_out(&input);
+        self.handle_super_peel_out(input);
 
         self.apply_slope_factor(delta);
 
         let can_input = !(self.state.is_crouching() || self.state.is_spindashing())
             && self.super_peel_out_state == SuperPeeloutState::NotCharged;
 
-        if can_input && self.handle_jump(&input) {
+        if can_input && self.handle_jump(input) {
             self.update_position(delta);
             return;
         }
         if can_input {
-            self.ground_accelerate(&input, delta);
-            self.apply_friction(&input, delta);
+            self.ground_accelerate(input, delta);
+            self.apply_friction(input, delta);
         }
 
-        self.handle_crouch(&input);
-        self.handle_look_up(&input);
+        self.handle_crouch(input);
+        self.handle_look_up(input);
 
-        self.check_walls();
+        let on_terrain = self.solid_object_to_stand_on.is_none() && !self.get_object_control();
+        if !on_terrain {
+            // Tiles set the surface when the floor is checked, objects can change it at any time
+            self.surface = SurfaceProperties {
+                surface_velocity: self.standing_surface_velocity(),
+                ..SurfaceProperties::default()
+            };
+        }
+
+        if !self.get_object_control() {
+            // The conveyor motion happens before the wall check so that walls stop it too
+            self.apply_surface_velocity(delta);
+            self.check_walls();
+        }
 
         self.update_animation();
 
-        if self.solid_object_to_stand_on.is_none() {
+        if on_terrain {
             self.check_floor();
         }
 
-        self.check_rolling(&input);
+        // While invulnerable the character keeps moving on the surface
+        if self.surface.hurt && self.hurt_by_surface() {
+            return;
+        }
+
+        self.check_rolling(input);
 
         self.update_velocity();
 
@@ -54,40 +75,34 @@ impl Character {
         self.handle_slipping();
     }
 
-    fn handle_crouch(&mut self, input: &Gd<Input>) {
-        if !self.state.is_spindashing()
-            && input.is_action_pressed("roll")
-            && self.ground_speed.abs() <= 1.0
-        {
+    fn handle_crouch(&mut self, input: CharacterInput) {
+        if !self.state.is_spindashing() && input.roll && self.ground_speed.abs() <= 1.0 {
             self.ground_speed = 0.0;
             self.set_state(State::Crouch);
-        } else if self.state.is_crouching() && !input.is_action_pressed("roll") {
+        } else if self.state.is_crouching() && !input.roll {
             self.set_state(State::Idle);
         }
     }
 
-    fn handle_look_up(&mut self, input: &Gd<Input>) {
-        if !self.state.is_super_peel_out()
-            && input.is_action_pressed("up")
-            && self.ground_speed.abs() <= 1.0
-        {
+    fn handle_look_up(&mut self, input: CharacterInput) {
+        if !self.state.is_super_peel_out() && input.up && self.ground_speed.abs() <= 1.0 {
             self.ground_speed = 0.0;
             self.set_state(State::LookUp);
-        } else if self.state.is_looking_up() && !input.is_action_pressed("up") {
+        } else if self.state.is_looking_up() && !input.up {
             self.set_state(State::Idle);
         }
     }
     /// From <https://info.sonicretro.org/SPG:Special_Abilities#Dash_.28Super_Peel_Out.29>
-    fn handle_super_peel_out(&mut self, input: &Gd<Input>) {
+    fn handle_super_peel_out(&mut self, input: CharacterInput) {
         if !self.has_super_peel_out {
             return;
         }
 
-        let is_up_pressed = input.is_action_pressed("up");
+        let is_up_pressed = input.up;
         let direction = if self.get_flip_h() { -1.0 } else { 1.0 };
         match self.super_peel_out_state {
             SuperPeeloutState::NotCharged => {
-                if is_up_pressed && input.is_action_pressed("jump") {
+                if is_up_pressed && input.jump {
                     self.set_state(State::SuperPeelOut);
                     self.super_peel_out_state = SuperPeeloutState::Charging { timer: 30 }
                 }
@@ -125,11 +140,11 @@ impl Character {
         }
     }
     /// From <https://info.sonicretro.org/SPG:Special_Abilities#Spindash_.28Sonic_2.2C_3.2C_.26_K.29>
-    fn handle_spindash(&mut self, input: &Gd<Input>, delta: f32) {
+    fn handle_spindash(&mut self, input: CharacterInput, delta: f32) {
         match self.spindash_style {
             SpindashStyle::Genesis => {
                 let direction = if self.get_flip_h() { -1.0 } else { 1.0 };
-                let is_jump_just_pressed = input.is_action_just_pressed("jump");
+                let is_jump_just_pressed = input.jump_just_pressed;
 
                 match self.spindash_genesis_state {
                     SpindashGenesisState::NotCharged => {
@@ -156,7 +171,7 @@ impl Character {
                             }
                         }
                         *charge = charge.clamp(0.0, 8.0);
-                        if !input.is_action_pressed("roll") {
+                        if !input.roll {
                             self.ground_speed = (8.0 + charge.floor() / 2.0) * direction;
                             self.set_state(State::RollingBall);
                             self.spindash_genesis_state = SpindashGenesisState::NotCharged;
@@ -170,8 +185,8 @@ impl Character {
             }
             // From <https://info.sonicretro.org/SPG:Special_Abilities#Spindash_.28Sonic_CD.29>
             SpindashStyle::CD => {
-                let jump_pressed = input.is_action_pressed("jump");
-                let roll_released = !input.is_action_pressed("roll");
+                let jump_pressed = input.jump;
+                let roll_released = !input.roll;
 
                 let direction = if self.get_flip_h() { -1.0 } else { 1.0 };
                 match self.spindash_cd_state {
@@ -216,8 +231,8 @@ impl Character {
         }
     }
 
-    fn check_rolling(&mut self, input: &Gd<Input>) {
-        if !self.state.is_rolling() && input.is_action_pressed("roll") && self.can_roll() {
+    fn check_rolling(&mut self, input: CharacterInput) {
+        if !self.state.is_rolling() && (self.get_force_roll() || input.roll && self.can_roll()) {
             godot_print!("Rolling");
             self.set_state(State::RollingBall);
         }
@@ -225,13 +240,46 @@ impl Character {
     /// From <https://info.sonicretro.org/SPG:Rolling#Criteria>
     fn check_unrolling(&mut self) {
         if self.state.is_rolling() && self.ground_speed.abs() < 0.5 {
-            godot_print!("Unrolling");
-            self.set_state(State::Idle);
+            if self.get_force_roll() {
+                // Sonic 2 gives an extra push instead of letting the character stop in pinball mode
+                let direction = if self.get_flip_h() { -1.0 } else { 1.0 };
+                let speed = self
+                    .force_roll_sources
+                    .values()
+                    .copied()
+                    .fold(0.0, f32::max);
+                self.ground_speed = speed * direction;
+                godot_print!("Keep rolling");
+            } else {
+                godot_print!("Unrolling");
+                self.set_state(State::Idle);
+            }
+        }
+    }
+
+    /// Applies the properties of the solid tile the character is standing on
+    pub(super) fn set_surface(&mut self, surface: SurfaceProperties) {
+        self.surface = surface;
+        if surface.collision_layer != 0 && surface.collision_layer != self.collision_layer {
+            godot_print!("Surface switches layer to {}", surface.collision_layer);
+            self.set_collision_layer(surface.collision_layer);
+            self.update_sensors();
         }
     }
 
+    /// Damaging tiles like lava knock the character back, against the direction it was moving.
+    /// Returns false if it was invulnerable
+    fn hurt_by_surface(&mut self) -> bool {
+        godot_print!("Hurt by surface");
+        let hazard_x = self.global_position().x + self.ground_speed.signum();
+        self.hurt_from(hazard_x)
+    }
+
     /// From <https://info.sonicretro.org/SPG:Slope_Physics#Falling_and_Slipping_Down_Slopes>
     fn handle_slipping(&mut self) {
+        if self.surface.sticky || self.get_object_control() {
+            return;
+        }
         if self.control_lock_timer <= 0 {
             // Slipping check
             if self.ground_speed.abs() < 2.5 && self.is_slipping() {
@@ -259,6 +307,7 @@ impl Character {
             if self.should_snap_to_floor(result) {
                 self.snap_to_floor(result.distance);
                 self.set_ground_angle_from_result(result);
+                self.set_surface(result.surface);
             } else {
                 godot_print!("Detach from floor: Shouldn't snap");
                 self.set_grounded(false);
@@ -281,15 +330,17 @@ impl Character {
     /// From <https://info.sonicretro.org/SPG:Slope_Collision#Push_Sensors_.28Grounded.29>
     fn check_walls(&mut self) {
         // Wall checking
+        // The floor moves the character too, so the walls are checked in the direction of both
+        let speed = self.ground_speed + self.surface.surface_velocity;
 
         if self.should_activate_wall_sensors() {
-            if self.ground_speed > 0.0 {
+            if speed > 0.0 {
                 if let Some(result) = self.wall_right_sensor_check(false) {
                     if result.distance < 0.0 {
                         self.grounded_right_wall_collision(result.distance);
                     }
                 }
-            } else if self.ground_speed < 0.0 {
+            } else if speed < 0.0 {
                 if let Some(result) = self.wall_left_sensor_check(false) {
                     if result.distance < 0.0 {
                         self.grounded_left_wall_collision(result.distance);
@@ -300,9 +351,9 @@ impl Character {
     }
 
     /// From <https://info.sonicretro.org/SPG:Jumping>
-    fn handle_jump(&mut self, input: &Gd<Input>) -> bool {
+    fn handle_jump(&mut self, input: CharacterInput) -> bool {
         // Jump Check
-        if input.is_action_just_pressed("jump") && self.can_jump() {
+        if input.jump_just_pressed && self.can_jump() {
             let (sin, cos) = self.ground_angle.sin_cos();
             self.velocity.x -= self.jump_force * sin;
             self.velocity.y -= self.jump_force * cos;
@@ -320,12 +371,11 @@ impl Character {
     }
 
     /// From <https://info.sonicretro.org/SPG:Running#Friction>
-    fn apply_friction(&mut self, input: &Gd<Input>, delta: f32) {
+    fn apply_friction(&mut self, input: CharacterInput, delta: f32) {
         // Optional fix: use friction always when control lock is active
 
         // Friction
-        let horizontal_input_pressed =
-            input.is_action_pressed("left") || input.is_action_pressed("right");
+        let horizontal_input_pressed = input.left || input.right;
         if self.state.is_rolling() || !horizontal_input_pressed {
             godot_print!("Apply friction");
 
@@ -335,7 +385,7 @@ impl Character {
     }
 
     /// From <https://info.sonicretro.org/SPG:Running>
-    fn ground_accelerate(&mut self, input: &Gd<Input>, delta: f32) {
+    fn ground_accelerate(&mut self, input: CharacterInput, delta: f32) {
         let top_speed = if self.state.is_rolling() {
             self.roll_top_speed
         } else {
@@ -344,8 +394,7 @@ impl Character {
         if self.control_lock_timer <= 0 {
             let is_rolling = self.state.is_rolling();
             // Ground Acceleration
-            let horizontal_input = i32::from(input.is_action_pressed("right"))
-                - i32::from(input.is_action_pressed("left"));
+            let horizontal_input = input.horizontal();
             if horizontal_input < 0 {
                 if self.ground_speed > 0.0 {
                     // Turn around
@@ -365,7 +414,7 @@ impl Character {
                     }
                 } else if self.ground_speed > -self.top_speed && !is_rolling {
                     godot_print!("Accelerate left");
-                    self.ground_speed -= self.acceleration * delta;
+                    self.ground_speed -= self.current_acceleration() * delta;
                     // Cap velocity
                     self.ground_speed = self.ground_speed.max(-top_speed);
                 }
@@ -390,7 +439,7 @@ impl Character {
                     }
                 } else if self.ground_speed < top_speed && !is_rolling {
                     godot_print!("Accelerate right");
-                    self.ground_speed += self.acceleration * delta;
+                    self.ground_speed += self.current_acceleration() * delta;
                     self.ground_speed = self.ground_speed.min(top_speed);
                 }
 
@@ -0,0 +1,33 @@
+use godot::prelude::*;
+
+/// Buttons the character reacts to, read once per frame so that the character
+/// can be driven either by the keyboard or by a CPU controller (like a `Sidekick`)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct CharacterInput {
+    pub left: bool,
+    pub right: bool,
+    pub up: bool,
+    pub roll: bool,
+    pub jump: bool,
+    pub jump_just_pressed: bool,
+}
+
+impl CharacterInput {
+    /// Reads the current state of the input actions, `prefix` selects the actions of a specific player (like `p2_`)
+    pub fn from_actions(input: &Gd<Input>, prefix: &GString) -> Self {
+        let action = |name: &str| StringName::from(format!("{prefix}{name}"));
+        Self {
+            left: input.is_action_pressed(&action("left")),
+            right: input.is_action_pressed(&action("right")),
+            up: input.is_action_pressed(&action("up")),
+            roll: input.is_action_pressed(&action("roll")),
+            jump: input.is_action_pressed(&action("jump")),
+            jump_just_pressed: input.is_action_just_pressed(&action("jump")),
+        }
+    }
+    /// -1 when holding left, 1 when holding right, 0 when holding both or none
+    pub fn horizontal(self) -> i32 {
+        i32::from(self.right) - i32::from(self.left)
+    }
+}
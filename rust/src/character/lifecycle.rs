@@ -1,11 +1,11 @@
 use godot::{classes::ThemeDb, prelude::*};
 
-use crate::character::{
-    godot_api::{SolidObjectKind, State},
-    Character,
+use crate::{
+    character::{godot_api::State, Character},
+    object_manager::ObjectManager,
 };
 
-use super::SuperPeeloutState;
+use super::{input::CharacterInput, SuperPeeloutState};
 
 // Genesis runs at 60 fps
 const FPS: f32 = 60.0;
@@ -37,7 +37,20 @@ impl INode2D for Character {
             }
         }
     }
+    fn ready(&mut self) {
+        let node = self.base().clone().upcast::<Node>();
+        self.scheduled = ObjectManager::find(&node).is_some();
+    }
     fn physics_process(&mut self, delta: f64) {
+        // The object manager steps the character at the right point of the frame
+        if !self.scheduled {
+            self.physics_step(delta);
+        }
+    }
+}
+impl Character {
+    /// Moves the character for one frame
+    pub(crate) fn physics_step(&mut self, delta: f64) {
         if self.debug_draw {
             self.base_mut().queue_redraw();
         }
@@ -49,7 +62,12 @@ impl INode2D for Character {
             delta as f32 * FPS
         };
 
+        if !self.cpu_controlled {
+            self.input = CharacterInput::from_actions(&Input::singleton(), &self.input_prefix);
+        }
+
         self.handle_invulnerability();
+        self.check_push_contact();
         self.stand_on_solid_object();
         if self.is_grounded {
             self.grounded(delta);
@@ -57,49 +75,20 @@ impl INode2D for Character {
             self.airborne(delta);
         }
     }
-}
-impl Character {
     /// From: <https://info.sonicretro.org/SPG:Solid_Objects#Standing_On_Solid_Objects>
+    /// Objects have already moved this frame, so the character is carried by how the object moved
+    /// (also rotating and scaling) and then placed on the current top of the object
     fn stand_on_solid_object(&mut self) {
         let Some(solid_object) = &self.solid_object_to_stand_on else {
             return;
         };
-        let mut position = self.global_position();
-
-        let (object_position, obj_width_radius, object_top_position, velocity) = match solid_object
-        {
-            SolidObjectKind::Simple(object) => {
-                let velocity = object.bind().get_velocity();
-                let object_position = object.bind().collision_shape_global_position() + velocity;
-                let obj_width_radius = object.bind().get_width_radius();
-                let obj_height_radius = object.bind().get_height_radius();
-                let object_top_position =
-                    object_position.y - obj_height_radius - self.height_radius - 1.0;
-                (
-                    object_position,
-                    obj_width_radius,
-                    object_top_position,
-                    velocity,
-                )
-            }
-            SolidObjectKind::Sloped(object) => {
-                let velocity = object.bind().get_velocity();
-
-                let object_position = object.bind().global_center() + velocity;
-                let obj_width_radius = object.bind().width_radius();
+        let motion = solid_object.with_body(|object| object.motion());
+        let mut position = motion.carry(self.global_position());
 
-                let (top, _) = object.bind().current_top_bottom(position);
-                let object_top_position = top - self.height_radius - 1.0;
-                (
-                    object_position,
-                    obj_width_radius,
-                    object_top_position,
-                    velocity,
-                )
-            }
-        };
+        let (object_position, obj_width_radius, object_top) =
+            solid_object.with_body(|object| object.standing_surface(position));
+        let object_top_position = object_top - self.height_radius - 1.0;
 
-        position.x += velocity.x;
         position.y = object_top_position;
         self.base_mut().set_global_position(position);
         self.set_grounded(true);
@@ -140,6 +129,27 @@ impl Character {
         self.set_global_position(position);
     }
 
+    /// Moves the character along the floor without changing `ground_speed`, like conveyor belts.
+    /// Tiles and objects both give it in pixels per frame at 60 FPS, so it is scaled by `delta` like `velocity`
+    pub(super) fn apply_surface_velocity(&mut self, delta: f32) {
+        let surface_velocity = self.surface.surface_velocity;
+        if !self.is_grounded || surface_velocity == 0.0 {
+            return;
+        }
+        let direction = Vector2::new(self.ground_angle.cos(), -self.ground_angle.sin());
+        godot_print!("Surface velocity: {surface_velocity}");
+        let position = self.global_position() + direction * surface_velocity * delta;
+        self.set_global_position(position);
+    }
+    /// Surface velocity of the object the character stands on, 0 when not on an object
+    pub(super) fn standing_surface_velocity(&self) -> f32 {
+        self.solid_object_to_stand_on
+            .as_ref()
+            .map_or(0.0, |object| {
+                object.with_body(|body| body.surface_velocity())
+            })
+    }
+
     /// From <https://info.sonicretro.org/SPG:Animations#Animation_Rules>
     pub(super) fn update_animation(&mut self) {
         match self.state {
@@ -165,14 +175,12 @@ impl Character {
                 }
             }
             State::Pushing => {
-                let input = Input::singleton();
-                let horizontal_input = i32::from(input.is_action_pressed("right"))
-                    - i32::from(input.is_action_pressed("left"));
+                let horizontal_input = self.input.horizontal();
                 if horizontal_input == 0
                     || horizontal_input > 0 && self.facing_left()
                     || horizontal_input < 0 && !self.facing_left()
                 {
-                    self.set_state(State::Idle);
+                    self.end_pushing();
                 }
             }
             State::SuperPeelOut => {
@@ -5,7 +5,10 @@ use std::f32::consts::TAU;
 use crate::sensor::{DetectionResult, Direction};
 
 use super::Character;
-use godot::{classes::RectangleShape2D, prelude::*};
+use godot::{
+    classes::{CollisionShape2D, RectangleShape2D},
+    prelude::*,
+};
 use real_consts::{FRAC_PI_2, PI};
 
 pub fn inverse_lerp(a: f32, b: f32, v: f32) -> f32 {
@@ -175,6 +178,23 @@ impl Character {
             shape.set_size(size);
         }
     }
+    /// Global rectangle of the hitbox, used by objects that react to touching the character like bumpers
+    pub(crate) fn hitbox_rect(&self) -> Rect2 {
+        let size = self
+            .hitbox_shape
+            .as_deref()
+            .and_then(CollisionShape2D::get_shape)
+            .and_then(|shape| shape.try_cast::<RectangleShape2D>().ok())
+            .map_or_else(
+                || Vector2::new(15.0, self.height_radius * 2.0 - 3.0),
+                |rect| rect.get_size(),
+            );
+        let center = self.hitbox_shape.as_ref().map_or_else(
+            || self.global_position(),
+            |shape| shape.get_global_position(),
+        );
+        Rect2::new(center - size / 2.0, size)
+    }
     pub(super) fn set_hitbox_size(&mut self, size: Vector2) {
         if let Some(collision_shape) = self.hitbox_shape.as_deref_mut() {
             if let Some(mut rect) = collision_shape
@@ -190,6 +210,12 @@ impl Character {
             sprites.play_ex().name(animation).done();
         }
     }
+    pub(super) fn has_animation(&self, animation: &str) -> bool {
+        self.sprites
+            .as_ref()
+            .and_then(|sprites| sprites.get_sprite_frames())
+            .is_some_and(|frames| frames.has_animation(animation))
+    }
     pub(super) fn facing_left(&self) -> bool {
         if let Some(sprites) = &self.sprites {
             return sprites.is_flipped_h();
@@ -208,6 +234,9 @@ impl Character {
     }
     /// From <https://info.sonicretro.org/SPG:Slope_Collision#Jump_Check>
     pub(super) fn can_jump(&self) -> bool {
+        if self.get_jump_locked() {
+            return false;
+        }
         if let Some(result) = self.ceiling_check(false) {
             return result.distance >= 6.0;
         }
@@ -295,19 +324,24 @@ impl Character {
             self.slope_factor_normal
         }
     }
+    pub(super) const fn current_acceleration(&self) -> f32 {
+        self.acceleration * self.surface.friction
+    }
     pub(super) const fn current_friction(&self) -> f32 {
-        if self.state.is_rolling() {
+        let friction = if self.state.is_rolling() {
             self.roll_friction
         } else {
             self.friction
-        }
+        };
+        friction * self.surface.friction
     }
     pub(super) const fn current_deceleration(&self) -> f32 {
-        if self.state.is_rolling() {
+        let deceleration = if self.state.is_rolling() {
             self.roll_deceleration
         } else {
             self.deceleration
-        }
+        };
+        deceleration * self.surface.friction
     }
     pub(super) fn current_motion_direction(&self) -> MotionDirection {
         MotionDirection::from_velocity(self.velocity)
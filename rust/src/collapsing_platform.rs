@@ -0,0 +1,289 @@
+#![allow(clippy::needless_pass_by_value)]
+use godot::{
+    classes::{Engine, Sprite2D},
+    prelude::*,
+};
+
+use crate::{
+    character::Character,
+    solid_object::{Collision, CollisionInfo, SolidBox, SolidObject},
+};
+
+/// Size of the fragments the platform breaks into
+const FRAGMENT_SIZE: f32 = 16.0;
+/// Frames the fragments keep falling before being removed
+const FRAGMENT_LIFETIME: i32 = 256;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum CollapseState {
+    #[default]
+    Idle,
+    /// A character stood on the platform, counting down before breaking
+    Shaking {
+        timer: i32,
+    },
+    /// Broken into fragments, the timer counts the frames since breaking
+    Collapsing {
+        timer: i32,
+    },
+    Collapsed,
+}
+
+#[derive(Debug)]
+struct Fragment {
+    sprite: Gd<Sprite2D>,
+    column: usize,
+    delay: i32,
+    speed: f32,
+}
+
+/// From <https://info.sonicretro.org/SPG:Game_Objects>
+/// Collapsing ledges (Green Hill) and crumbling floors (Marble Zone): after a character stands on the `solid_object`,
+/// the sprite breaks into 16x16 fragments that fall one column at a time.
+/// The solid object shrinks together with the columns that are still standing,
+/// so a character is released exactly when the column under it falls
+#[derive(GodotClass)]
+#[class(init, base=Node2D)]
+pub struct CollapsingPlatform {
+    /// Solid object the characters stand on
+    #[export]
+    solid_object: Option<Gd<SolidObject>>,
+    /// Sprite that is broken into fragments
+    #[export]
+    sprite: Option<Gd<Sprite2D>>,
+    /// Frames between being stood on and breaking
+    #[export]
+    #[init(val = 7)]
+    collapse_delay: i32,
+    /// Frames between a column falling and the next one
+    #[export]
+    #[init(val = 4)]
+    column_delay: i32,
+    /// Set to true to make the columns fall starting from the right
+    #[export]
+    collapse_from_right: bool,
+    #[export]
+    #[init(val = 0.21875)]
+    fall_gravity: f32,
+
+    state: CollapseState,
+    fragments: Vec<Fragment>,
+    /// Columns that have not fallen yet
+    standing_columns: Vec<bool>,
+    /// Left edge and width radius of the solid object before collapsing
+    solid_left: f32,
+    solid_width_radius: f32,
+    base: Base<Node2D>,
+}
+
+#[godot_api]
+impl INode2D for CollapsingPlatform {
+    fn ready(&mut self) {
+        if Engine::singleton().is_editor_hint() {
+            return;
+        }
+        let callable = self.base().callable("on_solid_object_collided");
+        if let Some(solid_object) = &mut self.solid_object {
+            solid_object.connect("collided", &callable);
+        }
+    }
+
+    fn physics_process(&mut self, _delta: f64) {
+        match self.state {
+            CollapseState::Idle | CollapseState::Collapsed => {}
+            CollapseState::Shaking { timer } => {
+                if timer <= 0 {
+                    self.break_apart();
+                    self.state = CollapseState::Collapsing { timer: 0 };
+                } else {
+                    self.state = CollapseState::Shaking { timer: timer - 1 };
+                }
+            }
+            CollapseState::Collapsing { timer } => {
+                self.update_fragments(timer);
+                self.state = if timer >= self.last_delay() + FRAGMENT_LIFETIME {
+                    self.remove_fragments();
+                    CollapseState::Collapsed
+                } else {
+                    CollapseState::Collapsing { timer: timer + 1 }
+                };
+            }
+        }
+    }
+}
+
+#[godot_api]
+impl CollapsingPlatform {
+    #[signal]
+    fn collapsed();
+
+    #[func]
+    fn on_solid_object_collided(
+        &mut self,
+        collision: Collision,
+        _player: Gd<Node2D>,
+        _info: Gd<CollisionInfo>,
+    ) {
+        if collision == Collision::Up && self.state == CollapseState::Idle {
+            godot_print!("Platform starts collapsing");
+            self.state = CollapseState::Shaking {
+                timer: self.collapse_delay,
+            };
+        }
+    }
+}
+
+impl CollapsingPlatform {
+    /// Hides the sprite and replaces it with fragments, each column falls `column_delay` frames after the previous one
+    fn break_apart(&mut self) {
+        let Some(mut sprite) = self.sprite.clone() else {
+            return;
+        };
+        let Some(texture) = sprite.get_texture() else {
+            return;
+        };
+        let region = if sprite.is_region_enabled() {
+            sprite.get_region_rect()
+        } else {
+            Rect2::new(Vector2::ZERO, texture.get_size())
+        };
+        let mut top_left = sprite.get_offset();
+        if sprite.is_centered() {
+            top_left -= region.size * 0.5;
+        }
+        let transform = sprite.get_global_transform();
+
+        #[allow(clippy::cast_sign_loss)]
+        let (columns, rows) = (
+            (region.size.x / FRAGMENT_SIZE).ceil() as usize,
+            (region.size.y / FRAGMENT_SIZE).ceil() as usize,
+        );
+        for column in 0..columns {
+            let order = if self.collapse_from_right {
+                columns - 1 - column
+            } else {
+                column
+            };
+            let delay = i32::try_from(order).unwrap_or(i32::MAX) * self.column_delay;
+            for row in 0..rows {
+                #[allow(clippy::cast_precision_loss)]
+                let cell = Vector2::new(column as f32, row as f32) * FRAGMENT_SIZE;
+                let size = (region.size - cell).coord_min(Vector2::splat(FRAGMENT_SIZE));
+                let mut fragment = Sprite2D::new_alloc();
+                fragment.set_texture(&texture);
+                fragment.set_region_enabled(true);
+                fragment.set_region_rect(Rect2::new(region.position + cell, size));
+                fragment.set_centered(false);
+                fragment.set_z_index(sprite.get_z_index());
+                self.base_mut().add_child(&fragment);
+                let mut fragment_transform = transform;
+                fragment_transform.origin = transform * (top_left + cell);
+                fragment.set_global_transform(fragment_transform);
+                self.fragments.push(Fragment {
+                    sprite: fragment,
+                    column,
+                    delay,
+                    speed: 0.0,
+                });
+            }
+        }
+        sprite.hide();
+
+        self.standing_columns = vec![true; columns];
+        if let Some(solid_object) = &self.solid_object {
+            let solid_object = solid_object.bind();
+            let radius = solid_object.get_width_radius();
+            self.solid_width_radius = radius;
+            self.solid_left = solid_object.collision_shape_global_position().x - radius;
+        }
+        self.base_mut().emit_signal("collapsed", &[]);
+    }
+
+    /// Makes the fragments fall once their delay has passed
+    fn update_fragments(&mut self, timer: i32) {
+        let mut fallen_columns = vec![];
+        for fragment in &mut self.fragments {
+            if timer < fragment.delay {
+                continue;
+            }
+            if timer == fragment.delay {
+                fallen_columns.push(fragment.column);
+            }
+            fragment.speed += self.fall_gravity;
+            let position = fragment.sprite.get_position() + Vector2::new(0.0, fragment.speed);
+            fragment.sprite.set_position(position);
+        }
+        if fallen_columns.is_empty() {
+            return;
+        }
+        for column in fallen_columns {
+            if let Some(standing) = self.standing_columns.get_mut(column) {
+                *standing = false;
+            }
+        }
+        self.update_solid_object();
+    }
+
+    /// Shrinks the solid object to the columns that are still standing and releases the characters above fallen columns
+    fn update_solid_object(&mut self) {
+        let Some(mut solid_object) = self.solid_object.clone() else {
+            return;
+        };
+        let columns = self.standing_columns.len();
+        if columns == 0 {
+            return;
+        }
+        #[allow(clippy::cast_precision_loss)]
+        let column_width = self.solid_width_radius * 2.0 / columns as f32;
+        let first = self.standing_columns.iter().position(|s| *s);
+        let last = self.standing_columns.iter().rposition(|s| *s);
+
+        // Release the riders standing over a fallen column
+        let node = self.base().clone().upcast::<Node>();
+        let object = solid_object.clone().upcast::<Node2D>();
+        for mut player in Character::players_in_tree(&node) {
+            if player.bind().get_standing_object() != Some(object.clone()) {
+                continue;
+            }
+            let x = player.get_global_position().x - self.solid_left;
+            #[allow(clippy::cast_sign_loss)]
+            let is_standing = x >= 0.0
+                && self
+                    .standing_columns
+                    .get((x / column_width) as usize)
+                    .is_some_and(|s| *s);
+            if !is_standing {
+                godot_print!("Released from collapsing platform");
+                player.bind_mut().clear_standing_objects();
+            }
+        }
+
+        let (Some(first), Some(last)) = (first, last) else {
+            // Everything fell
+            solid_object.queue_free();
+            self.solid_object = None;
+            return;
+        };
+        #[allow(clippy::cast_precision_loss)]
+        let (left, right) = (
+            self.solid_left + first as f32 * column_width,
+            self.solid_left + (last + 1) as f32 * column_width,
+        );
+        let center = (left + right) * 0.5;
+        let offset = center - solid_object.bind().collision_shape_global_position().x;
+        solid_object
+            .bind_mut()
+            .set_width_radius((right - left) * 0.5);
+        solid_object.bind_mut().shift_collision_shape(offset);
+    }
+
+    fn last_delay(&self) -> i32 {
+        self.fragments.iter().map(|f| f.delay).max().unwrap_or(0)
+    }
+
+    fn remove_fragments(&mut self) {
+        for mut fragment in self.fragments.drain(..) {
+            fragment.sprite.queue_free();
+        }
+    }
+}
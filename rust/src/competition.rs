@@ -0,0 +1,311 @@
+#![allow(clippy::needless_pass_by_value, clippy::cast_possible_wrap)]
+use godot::{classes::object::ConnectFlags, prelude::*};
+
+use crate::character::{godot_api::State, Character};
+
+/// Race progress of a single player
+#[derive(Debug, Clone, Copy, Default)]
+struct PlayerRecord {
+    lives: i32,
+    /// Frames since the start of the race, stops when finishing
+    frames: i32,
+    /// Rings collected during the whole race, even the ones lost afterwards
+    total_rings: i32,
+    last_rings: i32,
+    finished: bool,
+    /// True when the player reached the goal, false when it ran out of lives or time
+    reached_goal: bool,
+    spawn_position: Vector2,
+}
+
+/// Local multiplayer race, similar to the 2P versus mode of Sonic 2.
+/// Keeps rings, lives and timers for each player and decides the winner when everyone is done
+#[derive(GodotClass)]
+#[class(init, base=Node)]
+pub struct Competition {
+    /// Competing characters, the index in this array is the player number
+    #[export]
+    players: Array<Gd<Character>>,
+    /// Lives each player starts with
+    #[export]
+    #[init(val = 3)]
+    starting_lives: i32,
+    /// Frames the other players have to reach the goal after the first one does
+    #[export]
+    #[init(val = 60 * 60)]
+    finish_time_limit: i32,
+    /// Set to true so that each player can collect every ring once, instead of rings being shared
+    #[export]
+    #[var(get)]
+    #[init(val = true)]
+    per_player_rings: bool,
+
+    records: Vec<PlayerRecord>,
+    finish_countdown: Option<i32>,
+    race_over: bool,
+    base: Base<Node>,
+}
+
+#[godot_api]
+impl INode for Competition {
+    fn ready(&mut self) {
+        self.base_mut().add_to_group("competition");
+        let died = self.base().callable("on_player_died");
+        let rings_changed = self.base().callable("on_player_rings_changed");
+        #[allow(clippy::cast_sign_loss)]
+        let deferred = ConnectFlags::DEFERRED.ord() as u32;
+        self.records.clear();
+        for (index, mut player) in self.players.iter_shared().enumerate() {
+            let index = index as i32;
+            player.bind_mut().set_reload_on_death(false);
+            self.records.push(PlayerRecord {
+                lives: self.starting_lives,
+                spawn_position: player.get_global_position(),
+                ..Default::default()
+            });
+            // Deferred because the character is still borrowed when it dies,
+            // and the competition is still borrowed when it resets the rings of a respawned player
+            player
+                .connect_ex("died", &died.bindv(&varray![index]))
+                .flags(deferred)
+                .done();
+            player
+                .connect_ex("rings_changed", &rings_changed.bindv(&varray![index]))
+                .flags(deferred)
+                .done();
+        }
+    }
+
+    fn physics_process(&mut self, _delta: f64) {
+        if self.race_over {
+            return;
+        }
+        for record in self.records.iter_mut().filter(|r| !r.finished) {
+            record.frames += 1;
+        }
+        if let Some(countdown) = &mut self.finish_countdown {
+            *countdown -= 1;
+            if *countdown <= 0 {
+                godot_print!("Time over");
+                for record in &mut self.records {
+                    record.finished = true;
+                }
+                self.end_race();
+            }
+        }
+    }
+}
+
+#[godot_api]
+impl Competition {
+    #[signal]
+    fn lives_changed(player_index: i32, lives: i32);
+    #[signal]
+    fn player_finished(player_index: i32);
+    /// `results` has a dictionary for each player with `time`, `rings`, `total_rings`, `lives` and `reached_goal`,
+    /// `winner` is -1 when it is a draw
+    #[signal]
+    fn race_finished(results: Array<Dictionary>, winner: i32);
+
+    /// Called by the goal when a player reaches it
+    #[func]
+    fn reach_goal(&mut self, player: Gd<Character>) {
+        let index = self.player_index(player);
+        let Some(record) = usize::try_from(index)
+            .ok()
+            .and_then(|i| self.records.get_mut(i))
+        else {
+            return;
+        };
+        if record.finished {
+            return;
+        }
+        record.finished = true;
+        record.reached_goal = true;
+        self.base_mut()
+            .emit_signal("player_finished", &[index.to_variant()]);
+        if self.finish_countdown.is_none() {
+            self.finish_countdown = Some(self.finish_time_limit);
+        }
+        if self.records.iter().all(|r| r.finished) {
+            self.end_race();
+        }
+    }
+
+    /// Player number of a character, -1 if it is not competing
+    #[func]
+    fn player_index(&self, player: Gd<Character>) -> i32 {
+        self.players
+            .iter_shared()
+            .position(|p| p == player)
+            .map_or(-1, |i| i as i32)
+    }
+    /// Race time of a player in frames
+    #[func]
+    fn get_time(&self, player_index: i32) -> i32 {
+        self.record(player_index).map_or(0, |r| r.frames)
+    }
+    #[func]
+    fn get_lives(&self, player_index: i32) -> i32 {
+        self.record(player_index).map_or(0, |r| r.lives)
+    }
+    #[func]
+    fn is_finished(&self, player_index: i32) -> bool {
+        self.record(player_index).is_some_and(|r| r.finished)
+    }
+    /// Frames left before the race ends, -1 if no one has reached the goal yet
+    #[func]
+    fn get_finish_countdown(&self) -> i32 {
+        self.finish_countdown.unwrap_or(-1)
+    }
+
+    /// Visibility layer of things only the given player can see.
+    /// Layer 1 is shared by everyone, player 0 uses layer 2, player 1 layer 3 and so on
+    #[func]
+    #[allow(clippy::missing_const_for_fn)]
+    fn player_visibility_layer(player_index: i32) -> u32 {
+        1 << (player_index + 1)
+    }
+    /// Cull mask for the viewport of a player, hiding what only the other players can see
+    #[func]
+    fn canvas_cull_mask(&self, player_index: i32) -> u32 {
+        let mut mask = u32::MAX;
+        for i in 0..self.players.len() as i32 {
+            if i != player_index {
+                mask &= !Self::player_visibility_layer(i);
+            }
+        }
+        mask
+    }
+    /// Visibility layers that show an object only to the players that are not in `players`
+    #[func]
+    fn visibility_layers_without(&self, players: Array<Gd<Character>>) -> u32 {
+        let mut layers = 0;
+        for (i, player) in self.players.iter_shared().enumerate() {
+            if !players.contains(&player) {
+                layers |= Self::player_visibility_layer(i as i32);
+            }
+        }
+        layers
+    }
+
+    #[func]
+    fn on_player_rings_changed(&mut self, rings: i32, player_index: i32) {
+        let Some(record) = self.record_mut(player_index) else {
+            return;
+        };
+        if rings > record.last_rings {
+            record.total_rings += rings - record.last_rings;
+        }
+        record.last_rings = rings;
+    }
+
+    #[func]
+    fn on_player_died(&mut self, player_index: i32) {
+        let Some(mut player) = usize::try_from(player_index)
+            .ok()
+            .and_then(|i| self.players.get(i))
+        else {
+            return;
+        };
+        let Some(record) = self.record_mut(player_index) else {
+            return;
+        };
+        if record.finished {
+            return;
+        }
+        record.lives -= 1;
+        let lives = record.lives;
+        if lives > 0 {
+            // Respawn at the start
+            let spawn_position = record.spawn_position;
+            player.set_global_position(spawn_position);
+            let mut player = player.bind_mut();
+            player.clear_standing_objects();
+            player.set_velocity(Vector2::ZERO);
+            player.set_ground_speed(0.0);
+            player.set_state(State::Idle);
+            player.set_rings(0);
+        } else {
+            // Out of the race
+            record.finished = true;
+            player.hide();
+            player.set_physics_process(false);
+            player.bind_mut().set_object_collision(false);
+        }
+        self.base_mut().emit_signal(
+            "lives_changed",
+            &[player_index.to_variant(), lives.to_variant()],
+        );
+        if self.records.iter().all(|r| r.finished) {
+            self.end_race();
+        }
+    }
+}
+
+impl Competition {
+    fn record(&self, player_index: i32) -> Option<&PlayerRecord> {
+        self.records.get(usize::try_from(player_index).ok()?)
+    }
+    fn record_mut(&mut self, player_index: i32) -> Option<&mut PlayerRecord> {
+        self.records.get_mut(usize::try_from(player_index).ok()?)
+    }
+
+    fn end_race(&mut self) {
+        if self.race_over {
+            return;
+        }
+        self.race_over = true;
+        let results: Array<Dictionary> = self
+            .records
+            .iter()
+            .map(|r| {
+                dict! {
+                    "time": r.frames,
+                    "rings": r.last_rings,
+                    "total_rings": r.total_rings,
+                    "lives": r.lives,
+                    "reached_goal": r.reached_goal,
+                }
+            })
+            .collect();
+        let winner = self.winner();
+        godot_print!("Race finished, winner: {winner}");
+        self.base_mut().emit_signal(
+            "race_finished",
+            &[results.to_variant(), winner.to_variant()],
+        );
+    }
+
+    /// Like Sonic 2, each category (time, rings, total rings) gives a point to the best player,
+    /// whoever gets more points wins
+    fn winner(&self) -> i32 {
+        // Players that didn't reach the goal get the worst possible time
+        let times: Vec<i32> = self
+            .records
+            .iter()
+            .map(|r| if r.reached_goal { -r.frames } else { i32::MIN })
+            .collect();
+        let rings: Vec<i32> = self.records.iter().map(|r| r.last_rings).collect();
+        let total_rings: Vec<i32> = self.records.iter().map(|r| r.total_rings).collect();
+
+        let mut points = vec![0; self.records.len()];
+        for category in [times, rings, total_rings] {
+            if let Some(best) = best_index(&category) {
+                points[best] += 1;
+            }
+        }
+        best_index(&points).map_or(-1, |i| i as i32)
+    }
+}
+
+/// Index of the highest value, None if it is tied
+fn best_index(values: &[i32]) -> Option<usize> {
+    let max = *values.iter().max()?;
+    let mut best = values.iter().enumerate().filter(|(_, v)| **v == max);
+    let (index, _) = best.next()?;
+    if best.next().is_some() {
+        return None;
+    }
+    Some(index)
+}
@@ -0,0 +1,202 @@
+use std::{
+    collections::{HashMap, HashSet},
+    f32::consts::TAU,
+};
+
+use godot::{classes::Engine, prelude::*};
+
+use crate::{character::Character, object_manager::InteractiveObject};
+
+/// From <https://info.sonicretro.org/SPG:Game_Objects#Corkscrews>
+/// Emerald Hill Zone corkscrew (Sonic 2). There is no terrain, characters running through it fast enough
+/// have their y position mapped from their x position along one turn of a cosine, they fall off when they slow down
+/// or jump. The entrances are at both ends of the corkscrew, on the origin's height
+#[derive(GodotClass)]
+#[class(tool, init, base=Node2D)]
+pub struct Corkscrew {
+    /// Half of the horizontal length of the corkscrew
+    #[export]
+    #[init(val = 192.0)]
+    width_radius: f32,
+    /// How high the characters go at the middle of the corkscrew
+    #[export]
+    #[init(val = 64.0)]
+    height: f32,
+    /// Characters slower than this don't enter and fall off
+    #[export]
+    #[init(val = 6.0)]
+    min_speed: f32,
+    /// Draws the path of the corkscrew in the editor
+    #[export]
+    #[init(val = true)]
+    debug_path: bool,
+    /// Characters currently running through the corkscrew
+    players_inside: HashSet<InstanceId>,
+    /// Last x position of each character relative to the origin, fast characters can go past an entrance in a single frame
+    player_x: HashMap<InstanceId, f32>,
+    /// True when registered with an `ObjectManager`, which checks the characters
+    managed: bool,
+    base: Base<Node2D>,
+}
+
+#[godot_api]
+impl INode2D for Corkscrew {
+    fn ready(&mut self) {
+        if Engine::singleton().is_editor_hint() {
+            return;
+        }
+        self.register();
+    }
+    fn exit_tree(&mut self) {
+        if !Engine::singleton().is_editor_hint() {
+            // Characters inside a corkscrew that goes away get their control back
+            self.release_players();
+            self.unregister();
+        }
+    }
+    fn physics_process(&mut self, _delta: f64) {
+        if Engine::singleton().is_editor_hint() {
+            return;
+        }
+        self.process_unmanaged();
+    }
+    fn draw(&mut self) {
+        if !self.debug_path || !Engine::singleton().is_editor_hint() {
+            return;
+        }
+        #[allow(clippy::cast_precision_loss)]
+        let points: Vec<Vector2> = (0..=32)
+            .map(|i| {
+                let x = -self.width_radius + self.width_radius * 2.0 * i as f32 / 32.0;
+                Vector2::new(x, self.path_y(x))
+            })
+            .collect();
+        self.base_mut()
+            .draw_polyline(&PackedVector2Array::from(points.as_slice()), Color::WHITE);
+    }
+}
+
+impl InteractiveObject for Corkscrew {
+    fn node(&self) -> Gd<Node2D> {
+        self.base().clone()
+    }
+    fn is_managed(&self) -> bool {
+        self.managed
+    }
+    fn set_managed(&mut self, value: bool) {
+        self.managed = value;
+    }
+    fn interact_players(&mut self, players: &[Gd<Character>]) {
+        let origin = self.base().get_global_position();
+        let last_x = std::mem::take(&mut self.player_x);
+        for player in players {
+            let mut player = player.clone();
+            let id = player.instance_id();
+            let x = player.get_global_position().x - origin.x;
+            self.player_x.insert(id, x);
+            if self.players_inside.contains(&id) {
+                self.update_player(&mut player);
+            } else if self.can_enter(&player, last_x.get(&id).copied().unwrap_or(x)) {
+                self.enter(&mut player);
+            }
+        }
+    }
+    fn bounds(&self) -> Option<Rect2> {
+        let origin = self.base().get_global_position();
+        Some(Rect2::new(
+            origin - Vector2::new(self.width_radius, self.height + 32.0),
+            Vector2::new(self.width_radius * 2.0, self.height + 64.0),
+        ))
+    }
+    fn deactivate(&mut self) {
+        self.release_players();
+        self.player_x.clear();
+    }
+}
+
+#[godot_api]
+impl Corkscrew {
+    #[signal]
+    fn player_entered(player: Gd<Character>);
+    #[signal]
+    fn player_exited(player: Gd<Character>);
+}
+
+impl Corkscrew {
+    /// Height of the path relative to the entrances, `x` is relative to the center
+    fn path_y(&self, x: f32) -> f32 {
+        let t = (x + self.width_radius) / (self.width_radius * 2.0);
+        -self.height * (1.0 - (t * TAU).cos()) / 2.0
+    }
+
+    /// Grounded characters running fast enough towards the corkscrew from one of the entrances.
+    /// `last_x` is the x position relative to the origin in the previous frame
+    fn can_enter(&self, player: &Gd<Character>, last_x: f32) -> bool {
+        let delta = player.get_global_position() - self.base().get_global_position();
+        let player = player.bind();
+        let speed = player.get_ground_speed();
+        let inside = delta.x.abs() <= self.width_radius;
+        let at_left_entrance = inside && last_x.min(delta.x) < -self.width_radius + 16.0;
+        let at_right_entrance = inside && last_x.max(delta.x) > self.width_radius - 16.0;
+        player.get_is_grounded()
+            && speed.abs() >= self.min_speed
+            && ((at_left_entrance && speed > 0.0) || (at_right_entrance && speed < 0.0))
+            && (delta.y - self.path_y(delta.x)).abs() <= 32.0
+    }
+
+    fn enter(&mut self, player: &mut Gd<Character>) {
+        godot_print!("Enter corkscrew");
+        self.players_inside.insert(player.instance_id());
+        let source = self.base().clone().upcast::<Node>();
+        {
+            let mut player = player.bind_mut();
+            player.set_object_control(source, true);
+            player.set_ground_angle(0.0);
+        }
+        self.update_player(player);
+        self.base_mut()
+            .emit_signal("player_entered", &[player.to_variant()]);
+    }
+
+    fn update_player(&mut self, player: &mut Gd<Character>) {
+        let position = self.base().get_global_position();
+        let mut player_position = player.get_global_position();
+        let x = player_position.x - position.x;
+        let (grounded, speed) = {
+            let player = player.bind();
+            (player.get_is_grounded(), player.get_ground_speed())
+        };
+        if !grounded || x.abs() > self.width_radius {
+            self.exit(player);
+            return;
+        }
+        if speed.abs() < self.min_speed {
+            // Falls off the corkscrew
+            self.exit(player);
+            let mut player = player.bind_mut();
+            player.set_grounded(false);
+            player.set_velocity(Vector2::new(speed, 0.0));
+            return;
+        }
+        player_position.y = position.y + self.path_y(x) - player.bind().get_height_radius();
+        player.set_global_position(player_position);
+    }
+
+    /// Every character inside gets its control back
+    fn release_players(&mut self) {
+        for id in std::mem::take(&mut self.players_inside) {
+            if let Ok(mut player) = Gd::<Character>::try_from_instance_id(id) {
+                self.exit(&mut player);
+            }
+        }
+    }
+
+    fn exit(&mut self, player: &mut Gd<Character>) {
+        godot_print!("Exit corkscrew");
+        self.players_inside.remove(&player.instance_id());
+        let source = self.base().clone().upcast::<Node>();
+        player.bind_mut().set_object_control(source, false);
+        self.base_mut()
+            .emit_signal("player_exited", &[player.to_variant()]);
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::HashSet;
+
+use godot::{
+    classes::{CollisionShape2D, Engine, RectangleShape2D},
+    prelude::*,
+};
+
+use crate::{character::Character, object_manager::InteractiveObject};
+
+/// Pinball mode zone, like the tubes of Chemical Plant Zone or the pinball areas of Casino Night Zone (Sonic 2).
+/// Characters inside keep rolling and get pushed at `min_speed` when they are about to stop,
+/// jumping can be disabled with `allow_jump`
+#[derive(GodotClass)]
+#[class(tool, init, base=Node2D)]
+pub struct ForcedRollZone {
+    #[export(range = (0.0, 1000.0, 1.0, or_greater))]
+    #[var(get, set = set_width_radius)]
+    #[init(val = 32.0)]
+    width_radius: f32,
+    #[export(range = (0.0, 1000.0, 1.0, or_greater))]
+    #[var(get, set = set_height_radius)]
+    #[init(val = 32.0)]
+    height_radius: f32,
+    /// Ground speed given to characters that are about to stop rolling
+    #[export]
+    #[init(val = 4.0)]
+    min_speed: f32,
+    #[export]
+    #[init(val = true)]
+    allow_jump: bool,
+    /// Collision shape only for debug purposes, we don't use Godot collision detection
+    #[export]
+    collision_shape: Option<Gd<CollisionShape2D>>,
+    /// Characters currently inside the zone
+    players_inside: HashSet<InstanceId>,
+    /// True when registered with an `ObjectManager`, which checks the characters
+    managed: bool,
+    base: Base<Node2D>,
+}
+
+#[godot_api]
+impl INode2D for ForcedRollZone {
+    fn ready(&mut self) {
+        if Engine::singleton().is_editor_hint() {
+            return;
+        }
+        self.register();
+    }
+    fn exit_tree(&mut self) {
+        if !Engine::singleton().is_editor_hint() {
+            // Characters inside a zone that goes away leave pinball mode
+            self.release_players();
+            self.unregister();
+        }
+    }
+    fn physics_process(&mut self, _delta: f64) {
+        if Engine::singleton().is_editor_hint() {
+            return;
+        }
+        self.process_unmanaged();
+    }
+}
+
+impl InteractiveObject for ForcedRollZone {
+    fn node(&self) -> Gd<Node2D> {
+        self.base().clone()
+    }
+    fn is_managed(&self) -> bool {
+        self.managed
+    }
+    fn set_managed(&mut self, value: bool) {
+        self.managed = value;
+    }
+    fn interact_players(&mut self, players: &[Gd<Character>]) {
+        for player in players {
+            let mut player = player.clone();
+            let id = player.instance_id();
+            let inside = self.contains(player.get_global_position());
+            if inside && self.players_inside.insert(id) {
+                self.enter(&mut player);
+            } else if !inside && self.players_inside.remove(&id) {
+                self.exit(&mut player);
+            }
+        }
+    }
+    fn bounds(&self) -> Option<Rect2> {
+        let radius = Vector2::new(self.width_radius, self.height_radius);
+        Some(Rect2::new(
+            self.base().get_global_position() - radius,
+            radius * 2.0,
+        ))
+    }
+    fn deactivate(&mut self) {
+        self.release_players();
+    }
+}
+
+#[godot_api]
+impl ForcedRollZone {
+    #[signal]
+    fn player_entered(player: Gd<Character>);
+    #[signal]
+    fn player_exited(player: Gd<Character>);
+
+    #[func]
+    fn set_width_radius(&mut self, value: f32) {
+        self.width_radius = value;
+        self.update_shape();
+    }
+    #[func]
+    fn set_height_radius(&mut self, value: f32) {
+        self.height_radius = value;
+        self.update_shape();
+    }
+}
+
+impl ForcedRollZone {
+    fn contains(&self, point: Vector2) -> bool {
+        let delta = point - self.base().get_global_position();
+        delta.x.abs() <= self.width_radius && delta.y.abs() <= self.height_radius
+    }
+
+    fn enter(&mut self, player: &mut Gd<Character>) {
+        godot_print!("Enter forced roll zone");
+        let source = self.base().clone().upcast::<Node>();
+        {
+            let mut player = player.bind_mut();
+            player.set_force_roll_with_speed(source.clone(), self.min_speed);
+            player.set_jump_locked(source, !self.allow_jump);
+        }
+        self.base_mut()
+            .emit_signal("player_entered", &[player.to_variant()]);
+    }
+
+    fn exit(&mut self, player: &mut Gd<Character>) {
+        godot_print!("Exit forced roll zone");
+        let source = self.base().clone().upcast::<Node>();
+        {
+            let mut player = player.bind_mut();
+            player.set_force_roll(source.clone(), false);
+            player.set_jump_locked(source, false);
+        }
+        self.base_mut()
+            .emit_signal("player_exited", &[player.to_variant()]);
+    }
+
+    /// Every character inside leaves the zone
+    fn release_players(&mut self) {
+        for id in std::mem::take(&mut self.players_inside) {
+            if let Ok(mut player) = Gd::<Character>::try_from_instance_id(id) {
+                self.exit(&mut player);
+            }
+        }
+    }
+
+    /// Updates debug collision shape
+    fn update_shape(&self) {
+        if let Some(mut rect) = self
+            .collision_shape
+            .as_deref()
+            .and_then(CollisionShape2D::get_shape)
+            .and_then(|shape| shape.try_cast::<RectangleShape2D>().ok())
+        {
+            rect.set_size(Vector2::new(
+                self.width_radius * 2.0,
+                self.height_radius * 2.0,
+            ));
+        }
+    }
+}
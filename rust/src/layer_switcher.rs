@@ -1,9 +1,11 @@
+use std::collections::{HashMap, HashSet};
+
 use godot::{
-    classes::{CollisionShape2D, Engine, SegmentShape2D, ThemeDb},
+    classes::{CollisionPolygon2D, CollisionShape2D, Engine, Geometry2D, SegmentShape2D, ThemeDb},
     prelude::*,
 };
 
-use crate::character::Character;
+use crate::{character::Character, object_manager::ObjectManager};
 
 #[derive(GodotConvert, Var, Export, Default, Debug, PartialEq, Eq, Clone, Copy)]
 #[godot(via = GString)]
@@ -20,29 +22,68 @@ enum SwitcherTypeChange {
     ZIndex,
     Both,
 }
+#[derive(GodotConvert, Var, Export, Default, Debug, PartialEq, Eq, Clone, Copy)]
+#[godot(via = GString)]
+enum CrossingDirection {
+    #[default]
+    Both,
+    /// Only when going from the negative side to the positive side
+    ToPositive,
+    /// Only when going from the positive side to the negative side
+    ToNegative,
+}
+
+/// Characters that moved further than this in a frame were teleported (respawns, checkpoints),
+/// well over the speed cap of 16 pixels per frame
+const TELEPORT_DISTANCE: f32 = 64.0;
 
 /// From <https://info.sonicretro.org/SPG:Solid_Terrain#Layers>
-/// It switches either collision layer, Z-index, or both when going from one side to the other
+/// It switches either collision layer, Z-index, or both when going from one side to the other.
+/// Like the path swappers of Sonic 2 and 3, it can be limited to grounded, airborne or rolling characters,
+/// to characters crossing fast enough or in one direction, and to the first crossing of each character
+#[allow(clippy::struct_excessive_bools)]
 #[derive(GodotClass)]
 #[class(tool,init, base=Node2D)]
-struct LayerSwitcher {
+pub(crate) struct LayerSwitcher {
     /// Size of the layer switcher, it will not collide outside of it
     #[export(range = (0.0, 100.0,1.0,or_greater))]
     #[var(get,set = set_length)]
     #[init(val = 50.0)]
     length: f32,
 
-    /// Direction of the switcher, either horizontal or vertical
+    /// Direction of the switcher, either horizontal or vertical.
+    /// The switcher turns with the node, so that a rotated switcher can be put on ramps and loop entrances
     #[export]
     #[var(get,set = set_direction)]
     direction: Direction,
     /// Collision shape only for debug purposes, we don't use Godot collision detection
     #[export]
     collision_shape: Option<Gd<CollisionShape2D>>,
+    /// Region replacing the segment when set, the inside of the polygon is the positive side
+    /// and players switch when entering or leaving it
+    #[export]
+    region: Option<Gd<CollisionPolygon2D>>,
     base: Base<Node2D>,
     /// Set to true for making it switch layers only when the player is grounded (for example, at the top of a loop)
     #[export]
     grounded_only: bool,
+    /// Set to true for making it switch layers only when the player is airborne
+    #[export]
+    airborne_only: bool,
+    /// Set to true for making it switch layers only when the player is rolling
+    #[export]
+    rolling_only: bool,
+    /// Minimum speed for switching, the ground speed for grounded players and the speed across the switcher otherwise
+    #[export]
+    min_speed: f32,
+    /// Crossing direction that switches layers
+    #[export]
+    crossing_direction: CrossingDirection,
+    /// Set to true for switching each player only the first time it crosses
+    #[export]
+    one_shot: bool,
+    /// Players already switched by a one shot switcher
+    switched_players: HashSet<InstanceId>,
     /// Switcher functionality, either changes physics layer, z-index or both
     #[export]
     change_type: SwitcherTypeChange,
@@ -52,41 +93,65 @@ struct LayerSwitcher {
     #[export(flags_3d_physics)]
     negative_side_layer: u32,
     /// Negative: Left or Down
-    /// Z-index on the negative side
+    /// Z-index on the negative side, the sprite priority of the player
     #[export]
     negative_side_z_index: i32,
+    /// Negative: Left or Down
+    /// Visibility layer on the negative side when `switch_visibility_layer` is set,
+    /// so that cameras and viewports can cull the player with their canvas cull mask
+    #[export(flags_2d_render)]
+    #[init(val = 1)]
+    negative_side_visibility_layer: u32,
     /// Positive: Right or Up
     /// Physics layer on the positive side
     #[export(flags_3d_physics)]
     positive_side_layer: u32,
     /// Positive: Right or Up
-    /// Z-index on the positive side
+    /// Z-index on the positive side, the sprite priority of the player
     #[export]
     positive_side_z_index: i32,
+    /// Positive: Right or Up
+    /// Visibility layer on the positive side when `switch_visibility_layer` is set
+    #[export(flags_2d_render)]
+    #[init(val = 1)]
+    positive_side_visibility_layer: u32,
+    /// Set to true for also switching the visibility layer of the player
+    #[export]
+    switch_visibility_layer: bool,
+    /// Side where characters are assumed to start, true for the positive side
     #[export]
     current_side_of_player: bool,
+    /// Last side of each character, so that multiple characters can cross independently
+    player_sides: HashMap<InstanceId, bool>,
+    /// Last position of each character, fast characters can go through the switcher in a single frame
+    player_positions: HashMap<InstanceId, Vector2>,
     /// Set to true to change layers for the player even when moving it in the editor
     #[export]
     enable_in_editor: bool,
+    /// True when the `ObjectManager` checks the characters as the last step of its frame pipeline
+    scheduled: bool,
 }
 #[godot_api]
 impl INode2D for LayerSwitcher {
+    fn ready(&mut self) {
+        if Engine::singleton().is_editor_hint() {
+            return;
+        }
+        let node = self.base().clone().upcast::<Node>();
+        self.scheduled = ObjectManager::find(&node).is_some();
+        if self.scheduled {
+            self.base_mut().add_to_group("layer_switcher");
+        }
+    }
     fn physics_process(&mut self, _delta: f64) {
+        if self.scheduled {
+            return;
+        }
         if Engine::singleton().is_editor_hint() && !self.enable_in_editor {
             return;
         }
-        // Collision check
-        if let Some(mut player) = self.get_player() {
-            let is_player_on_positive_side = self.is_player_on_positive_side(&player);
-            let is_player_grounded = player.bind().get_is_grounded();
-            if self.check_player_entered(&player)
-                && self.current_side_of_player != is_player_on_positive_side
-                && (self.grounded_only == is_player_grounded || !self.grounded_only)
-            {
-                self.switch(&mut player, is_player_on_positive_side);
-            }
-            self.current_side_of_player = is_player_on_positive_side;
-        };
+        let node = self.base().clone().upcast::<Node>();
+        self.update_players(&Character::players_in_tree(&node));
     }
     fn draw(&mut self) {
         if !Engine::singleton().is_editor_hint() {
@@ -135,37 +200,138 @@ impl LayerSwitcher {
 }
 
 impl LayerSwitcher {
-    /// Boilerplate to fetch the player from node groups
-    fn get_player(&self) -> Option<Gd<Character>> {
-        self.base()
-            .get_tree()?
-            .get_first_node_in_group("player")?
-            .try_cast::<Character>()
-            .ok()
+    /// Switches the layers of the characters that crossed the switcher since last frame
+    pub fn update_players(&mut self, players: &[Gd<Character>]) {
+        self.prune_freed_players();
+        for player in players {
+            let mut player = player.clone();
+            let id = player.instance_id();
+            let current_side_of_player = self
+                .player_sides
+                .get(&id)
+                .copied()
+                .unwrap_or(self.current_side_of_player);
+            let is_player_on_positive_side = self.is_player_on_positive_side(&player);
+            let position = player.get_global_position();
+            let last_position = self
+                .player_positions
+                .insert(id, position)
+                .filter(|last_position| last_position.distance_to(position) <= TELEPORT_DISTANCE);
+            // Going in or out of a region always goes through its outline
+            let crossed = self.region.is_some()
+                || last_position.map_or_else(
+                    || self.check_player_entered(&player),
+                    |last_position| self.check_player_crossed(last_position, position),
+                );
+            if crossed
+                && current_side_of_player != is_player_on_positive_side
+                && self.check_conditions(&player, is_player_on_positive_side)
+            {
+                self.switch(&mut player, is_player_on_positive_side);
+                if self.one_shot {
+                    self.switched_players.insert(id);
+                }
+            }
+            self.player_sides.insert(id, is_player_on_positive_side);
+        }
+    }
+    /// Forgets the characters that were freed
+    fn prune_freed_players(&mut self) {
+        let is_valid = |id: &InstanceId| Gd::<Character>::try_from_instance_id(*id).is_ok();
+        self.player_sides.retain(|id, _| is_valid(id));
+        self.player_positions.retain(|id, _| is_valid(id));
+        self.switched_players.retain(is_valid);
+    }
+    pub(crate) const fn set_scheduled(&mut self, value: bool) {
+        self.scheduled = value;
+    }
+    /// Vertical physics layer switcher with its debug shape, used by generators like `Loop`
+    pub(crate) fn create(
+        length: f32,
+        negative_side_layer: u32,
+        positive_side_layer: u32,
+        grounded_only: bool,
+    ) -> Gd<Self> {
+        let mut collision_shape = CollisionShape2D::new_alloc();
+        collision_shape.set_name("CollisionShape2D");
+        collision_shape.set_shape(&SegmentShape2D::new_gd());
+        let mut switcher = Self::new_alloc();
+        switcher.add_child(&collision_shape);
+        {
+            let mut switcher = switcher.bind_mut();
+            switcher.collision_shape = Some(collision_shape);
+            switcher.negative_side_layer = negative_side_layer;
+            switcher.positive_side_layer = positive_side_layer;
+            switcher.grounded_only = grounded_only;
+            switcher.set_length(length);
+        }
+        switcher
     }
-    /// Returns true if the player has crossed the layer switcher
+    /// Returns true if the player is in the range of the layer switcher, used when its last position is unknown
     fn check_player_entered(&self, player: &Gd<Character>) -> bool {
-        let position = self.base().get_global_position();
-        let player_position = player.get_global_position();
-        let check_range = match self.direction {
-            Direction::Horizontal => (position.x - self.length)..=(position.x + self.length),
-            Direction::Vertical => (position.y - self.length)..=(position.y + self.length),
+        let (a, b) = self.global_segment();
+        let along = b - a;
+        let t = (player.get_global_position() - a).dot(along) / along.length_squared();
+        (0.0..=1.0).contains(&t)
+    }
+    /// Returns true if the player went through the switcher segment when moving from `from` to `to`,
+    /// so that it isn't skipped by characters moving more than its length in a frame
+    fn check_player_crossed(&self, from: Vector2, to: Vector2) -> bool {
+        let (a, b) = self.global_segment();
+        !Geometry2D::singleton()
+            .segment_intersects_segment(from, to, a, b)
+            .is_nil()
+    }
+    /// Returns true if the player that crossed to `positive_side` meets the conditions of the switcher
+    fn check_conditions(&self, player: &Gd<Character>, positive_side: bool) -> bool {
+        if self.switched_players.contains(&player.instance_id()) {
+            return false;
+        }
+        let direction_matches = match self.crossing_direction {
+            CrossingDirection::Both => true,
+            CrossingDirection::ToPositive => positive_side,
+            CrossingDirection::ToNegative => !positive_side,
         };
-        check_range.contains(match self.direction {
-            Direction::Horizontal => &player_position.x,
-            Direction::Vertical => &player_position.y,
-        })
+        let player = player.bind();
+        let grounded = player.get_is_grounded();
+        let speed = if grounded {
+            player.get_ground_speed()
+        } else if self.region.is_some() {
+            // A region has no direction to cross it in
+            player.velocity.length()
+        } else {
+            player.velocity.dot(self.normal())
+        };
+        direction_matches
+            && (!self.grounded_only || grounded)
+            && (!self.airborne_only || !grounded)
+            && (!self.rolling_only || player.state.is_rolling())
+            && speed.abs() >= self.min_speed
     }
     /// Returns true if the player is on the positive side (Up,Right) and
-    /// false if the player is on the negative side (Down,Left)
+    /// false if the player is on the negative side (Down,Left), the sides turn with the switcher.
+    /// With a region, the positive side is inside of it
     fn is_player_on_positive_side(&self, player: &Gd<Character>) -> bool {
         let player_position = player.get_global_position();
-        let position = self.base().get_global_position();
-        match self.direction {
-            // Negative y is up
-            Direction::Horizontal => player_position.y <= position.y,
-            Direction::Vertical => player_position.x >= position.x,
+        if let Some(region) = &self.region {
+            let local_position = region.get_global_transform().affine_inverse() * player_position;
+            return Geometry2D::singleton()
+                .is_point_in_polygon(local_position, &region.get_polygon());
         }
+        let (a, _) = self.global_segment();
+        (player_position - a).dot(self.normal()) >= 0.0
+    }
+    /// Segment of the switcher in global coordinates
+    fn global_segment(&self) -> (Vector2, Vector2) {
+        let transform = self.base().get_global_transform();
+        let (a, b) = self.segment();
+        (transform * a, transform * b)
+    }
+    /// Normal of the switcher pointing to the positive side
+    fn normal(&self) -> Vector2 {
+        let (a, b) = self.global_segment();
+        // The segments go down or right, their orthogonal points right or up
+        (b - a).normalized().orthogonal()
     }
     /// Changes physics layer and/or z-index for the player
     fn switch(&self, player: &mut Gd<Character>, current_player_side: bool) {
@@ -179,6 +345,13 @@ impl LayerSwitcher {
         } else {
             self.negative_side_z_index
         };
+        if self.switch_visibility_layer {
+            player.set_visibility_layer(if current_player_side {
+                self.positive_side_visibility_layer
+            } else {
+                self.negative_side_visibility_layer
+            });
+        }
         match self.change_type {
             SwitcherTypeChange::PhysicsLayer => player.bind_mut().set_collision_layer(layer),
             SwitcherTypeChange::ZIndex => player.set_z_index(z_index),
@@ -191,15 +364,21 @@ impl LayerSwitcher {
     }
     /// Updates debug collision shape
     fn update_segment(&self, mut segment: Gd<SegmentShape2D>) {
+        let (a, b) = self.segment();
+        segment.set_a(a);
+        segment.set_b(b);
+    }
+    /// End points of the switcher, relative to its position
+    fn segment(&self) -> (Vector2, Vector2) {
         match self.direction {
-            Direction::Vertical => {
-                segment.set_a(Vector2::new(0.0, -self.length));
-                segment.set_b(Vector2::new(0.0, self.length));
-            }
-            Direction::Horizontal => {
-                segment.set_a(Vector2::new(-self.length, 0.0));
-                segment.set_b(Vector2::new(self.length, 0.0));
-            }
+            Direction::Vertical => (
+                Vector2::new(0.0, -self.length),
+                Vector2::new(0.0, self.length),
+            ),
+            Direction::Horizontal => (
+                Vector2::new(-self.length, 0.0),
+                Vector2::new(self.length, 0.0),
+            ),
         }
     }
     fn get_segment_mut(&mut self) -> Option<Gd<SegmentShape2D>> {
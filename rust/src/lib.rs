@@ -1,12 +1,23 @@
 /// Most of the code in this project is based on <https://info.sonicretro.org/Sonic_Physics_Guide>
+mod bumper;
 mod character;
+mod collapsing_platform;
+mod competition;
+mod corkscrew;
+mod forced_roll_zone;
 
 pub mod layer_switcher;
 mod level_maker;
+mod loop_generator;
+mod object_manager;
+mod platform_mover;
+mod seesaw;
 pub mod sensor;
+mod sidekick;
 mod solid_object;
 
 mod solid_path_2d;
+mod spring;
 mod tool;
 mod vec3_ext;
 
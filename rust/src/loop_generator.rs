@@ -0,0 +1,172 @@
+#![allow(clippy::needless_pass_by_value)]
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use godot::{
+    classes::{Curve2D, Engine},
+    prelude::*,
+};
+
+use crate::{layer_switcher::LayerSwitcher, solid_path_2d::SolidPath2D};
+
+/// Closed curve going through `points`, its baked points are the collision polygon of a `SolidPath2D`
+fn closed_curve(points: &[Vector2]) -> Gd<Curve2D> {
+    let mut curve = Curve2D::new_gd();
+    curve.set_bake_interval(4.0);
+    for point in points.iter().chain(points.first()) {
+        curve.add_point(*point);
+    }
+    curve
+}
+
+/// From <https://info.sonicretro.org/SPG:Solid_Terrain#Layers>
+/// Generates the paths and layer switchers of a loop-de-loop, with the bottom of the loop at the origin.
+/// The floor is solid on both layers, the right half of the loop is on `layer_a` and the left half on `layer_b`,
+/// so characters running right go through the left half at the entry and through the right half at the exit.
+/// A switcher at the top changes the layer of the characters going over it, and the ones at the entry and the exit
+/// put back the layer for the side they leave to
+#[derive(GodotClass)]
+#[class(tool, init, base=Node2D)]
+pub struct Loop {
+    /// Radius of the inner surface of the loop
+    #[export(range = (16.0, 512.0, 1.0, or_greater))]
+    #[init(val = 128.0)]
+    radius: f32,
+    /// Length of the floor before the loop
+    #[export]
+    #[init(val = 64.0)]
+    entry_length: f32,
+    /// Length of the floor after the loop
+    #[export]
+    #[init(val = 64.0)]
+    exit_length: f32,
+    #[export]
+    #[init(val = 16.0)]
+    thickness: f32,
+    #[export(flags_2d_physics)]
+    #[init(val = 1)]
+    layer_a: u32,
+    #[export(flags_2d_physics)]
+    #[init(val = 2)]
+    layer_b: u32,
+    /// Points of each half of the loop
+    #[export(range = (2.0, 64.0, 1.0))]
+    #[init(val = 16)]
+    segments: i32,
+    /// Check to replace the children with a new loop, it is also generated when running without children
+    #[export]
+    #[var(get, set = set_generate)]
+    generate: bool,
+    base: Base<Node2D>,
+}
+
+#[godot_api]
+impl INode2D for Loop {
+    fn ready(&mut self) {
+        if !Engine::singleton().is_editor_hint() && self.base().get_child_count() == 0 {
+            self.generate_loop();
+        }
+    }
+}
+
+#[godot_api]
+impl Loop {
+    #[func]
+    fn set_generate(&mut self, value: bool) {
+        if value && self.base().is_inside_tree() {
+            self.generate_loop();
+        }
+    }
+}
+
+impl Loop {
+    fn generate_loop(&mut self) {
+        godot_print!("Generate loop");
+        for mut child in self.base().get_children().iter_shared() {
+            self.base_mut().remove_child(&child);
+            child.queue_free();
+        }
+        let (r, t) = (self.radius, self.thickness);
+        let (a, b) = (self.layer_a, self.layer_b);
+
+        let floor = closed_curve(&[
+            Vector2::new(-r - self.entry_length, 0.0),
+            Vector2::new(r + self.exit_length, 0.0),
+            Vector2::new(r + self.exit_length, t),
+            Vector2::new(-r - self.entry_length, t),
+        ]);
+        self.add_generated(
+            SolidPath2D::create(&floor, a | b).upcast(),
+            "Floor",
+            Vector2::ZERO,
+        );
+        // Angles go clockwise from the right, the bottom is at PI / 2
+        let right_half = self.half_curve(FRAC_PI_2, -FRAC_PI_2);
+        self.add_generated(
+            SolidPath2D::create(&right_half, a).upcast(),
+            "PathA",
+            Vector2::ZERO,
+        );
+        let left_half = self.half_curve(FRAC_PI_2 + PI, FRAC_PI_2);
+        self.add_generated(
+            SolidPath2D::create(&left_half, b).upcast(),
+            "PathB",
+            Vector2::ZERO,
+        );
+
+        let length = t + 32.0;
+        self.add_generated(
+            LayerSwitcher::create(length, b, a, true).upcast(),
+            "TopSwitcher",
+            Vector2::new(0.0, -2.0 * r),
+        );
+        self.add_generated(
+            LayerSwitcher::create(length, a, a, false).upcast(),
+            "EntrySwitcher",
+            Vector2::new(-r - t - 16.0, -length),
+        );
+        self.add_generated(
+            LayerSwitcher::create(length, b, a, false).upcast(),
+            "ExitSwitcher",
+            Vector2::new(r + t + 16.0, -length),
+        );
+    }
+
+    /// Strip between the inner and outer circles of the loop, going from angle `from` to angle `to`
+    fn half_curve(&self, from: f32, to: f32) -> Gd<Curve2D> {
+        let center = Vector2::new(0.0, -self.radius);
+        let segments = self.segments.max(2);
+        #[allow(clippy::cast_precision_loss)]
+        let angles: Vec<f32> = (0..=segments)
+            .map(|i| from + (to - from) * i as f32 / segments as f32)
+            .collect();
+        let inner = angles
+            .iter()
+            .map(|angle| center + Vector2::from_angle(*angle) * self.radius);
+        let outer = angles
+            .iter()
+            .rev()
+            .map(|angle| center + Vector2::from_angle(*angle) * (self.radius + self.thickness));
+        closed_curve(&inner.chain(outer).collect::<Vec<_>>())
+    }
+
+    /// Adds a generated child, owned by the edited scene so that it is saved with it
+    fn add_generated(&mut self, mut node: Gd<Node2D>, name: &str, position: Vector2) {
+        node.set_name(name);
+        node.set_position(position);
+        self.base_mut().add_child(&node);
+        let owner = self
+            .base()
+            .get_tree()
+            .and_then(|tree| tree.get_edited_scene_root());
+        if let Some(owner) = owner {
+            set_owner_recursive(node.upcast(), &owner);
+        }
+    }
+}
+
+fn set_owner_recursive(mut node: Gd<Node>, owner: &Gd<Node>) {
+    node.set_owner(owner);
+    for child in node.get_children().iter_shared() {
+        set_owner_recursive(child, owner);
+    }
+}
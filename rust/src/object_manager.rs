@@ -0,0 +1,519 @@
+#![allow(clippy::needless_pass_by_value)]
+use std::collections::{HashMap, HashSet};
+
+use godot::{
+    classes::{node::ProcessMode, Camera2D},
+    prelude::*,
+};
+
+use crate::{
+    bumper::{flipper::Flipper, Bumper},
+    character::Character,
+    corkscrew::Corkscrew,
+    forced_roll_zone::ForcedRollZone,
+    layer_switcher::LayerSwitcher,
+    solid_object::{
+        hazard_object::HazardObject, pushable_block::PushableBlock,
+        sloped_solid_object::SlopedSolidObject, SolidBody, SolidObject,
+    },
+};
+
+/// Size of a grid cell, the original games load objects in chunks of the same size
+const CELL_SIZE: f32 = 128.0;
+
+type Cell = (i32, i32);
+
+/// Objects that can be registered, solid objects are also collided with the characters
+/// and interactive objects check them after they moved
+#[derive(Debug, Clone)]
+pub enum ManagedObject {
+    Solid(Gd<SolidObject>),
+    Sloped(Gd<SlopedSolidObject>),
+    Pushable(Gd<PushableBlock>),
+    Hazard(Gd<HazardObject>),
+    Bumper(Gd<Bumper>),
+    Flipper(Gd<Flipper>),
+    ForcedRollZone(Gd<ForcedRollZone>),
+    Corkscrew(Gd<Corkscrew>),
+    /// Anything else that only needs to be activated and deactivated (rings, enemies)
+    Other(Gd<Node2D>),
+}
+
+impl ManagedObject {
+    pub fn from_node(node: Gd<Node2D>) -> Self {
+        let node = match node.try_cast::<SolidObject>() {
+            Ok(solid) => return Self::Solid(solid),
+            Err(node) => node,
+        };
+        let node = match node.try_cast::<SlopedSolidObject>() {
+            Ok(sloped) => return Self::Sloped(sloped),
+            Err(node) => node,
+        };
+        let node = match node.try_cast::<PushableBlock>() {
+            Ok(pushable) => return Self::Pushable(pushable),
+            Err(node) => node,
+        };
+        let node = match node.try_cast::<HazardObject>() {
+            Ok(hazard) => return Self::Hazard(hazard),
+            Err(node) => node,
+        };
+        let node = match node.try_cast::<Bumper>() {
+            Ok(bumper) => return Self::Bumper(bumper),
+            Err(node) => node,
+        };
+        let node = match node.try_cast::<Flipper>() {
+            Ok(flipper) => return Self::Flipper(flipper),
+            Err(node) => node,
+        };
+        let node = match node.try_cast::<ForcedRollZone>() {
+            Ok(zone) => return Self::ForcedRollZone(zone),
+            Err(node) => node,
+        };
+        match node.try_cast::<Corkscrew>() {
+            Ok(corkscrew) => Self::Corkscrew(corkscrew),
+            Err(node) => Self::Other(node),
+        }
+    }
+    fn node(&self) -> Gd<Node2D> {
+        match self {
+            Self::Solid(object) => object.clone().upcast(),
+            Self::Sloped(object) => object.clone().upcast(),
+            Self::Pushable(object) => object.clone().upcast(),
+            Self::Hazard(object) => object.clone().upcast(),
+            Self::Bumper(object) => object.clone().upcast(),
+            Self::Flipper(object) => object.clone().upcast(),
+            Self::ForcedRollZone(object) => object.clone().upcast(),
+            Self::Corkscrew(object) => object.clone().upcast(),
+            Self::Other(node) => node.clone(),
+        }
+    }
+    /// The solid object, which the characters collide with, `None` for other objects
+    fn with_body<R>(&mut self, f: impl FnOnce(&mut dyn SolidBody) -> R) -> Option<R> {
+        match self {
+            Self::Solid(object) => Some(f(&mut *object.bind_mut())),
+            Self::Sloped(object) => Some(f(&mut *object.bind_mut())),
+            Self::Pushable(object) => Some(f(&mut *object.bind_mut())),
+            Self::Hazard(object) => Some(f(&mut *object.bind_mut())),
+            _ => None,
+        }
+    }
+    /// The interactive object, which checks the characters, `None` for other objects
+    fn with_interactive<R>(
+        &mut self,
+        f: impl FnOnce(&mut dyn InteractiveObject) -> R,
+    ) -> Option<R> {
+        match self {
+            Self::Bumper(object) => Some(f(&mut *object.bind_mut())),
+            Self::Flipper(object) => Some(f(&mut *object.bind_mut())),
+            Self::ForcedRollZone(object) => Some(f(&mut *object.bind_mut())),
+            Self::Corkscrew(object) => Some(f(&mut *object.bind_mut())),
+            _ => None,
+        }
+    }
+    fn set_managed(&mut self, value: bool) {
+        self.with_body(|body| body.set_managed(value));
+        self.with_interactive(|object| object.set_managed(value));
+    }
+    fn update_velocity(&mut self) {
+        self.with_body(|body| body.update_velocity());
+    }
+    pub fn reset_velocity(&mut self) {
+        self.with_body(|body| body.reset_velocity());
+    }
+    fn collide_players(&mut self, players: &[Gd<Character>]) {
+        self.with_body(|body| body.collide_players(players));
+    }
+    fn interact_players(&mut self, players: &[Gd<Character>]) {
+        self.with_interactive(|object| object.interact_players(players));
+    }
+    fn deactivate(&mut self) {
+        self.with_interactive(|object| object.deactivate());
+    }
+    /// Cells covered by the object, large interactive objects can cover several of them
+    fn cells(&mut self) -> Vec<Cell> {
+        let position = self.node().get_global_position();
+        self.with_interactive(|object| object.bounds())
+            .flatten()
+            .map_or_else(
+                || vec![cell_of(position)],
+                |bounds| {
+                    let (min_x, min_y) = cell_of(bounds.position);
+                    let (max_x, max_y) = cell_of(bounds.end());
+                    (min_x..=max_x)
+                        .flat_map(|x| (min_y..=max_y).map(move |y| (x, y)))
+                        .collect()
+                },
+            )
+    }
+}
+
+/// Objects that act on the characters without being solid, like bumpers and zones.
+/// Registered ones are driven by the `ObjectManager` after the characters moved
+pub trait InteractiveObject {
+    fn node(&self) -> Gd<Node2D>;
+    /// True when registered with an `ObjectManager`
+    fn is_managed(&self) -> bool;
+    fn set_managed(&mut self, value: bool);
+    /// Checks every character for one frame
+    fn interact_players(&mut self, players: &[Gd<Character>]);
+    /// Global area of the object, objects larger than a cell (like zones) are active while any part of them is in a window.
+    /// `None` only uses the position
+    fn bounds(&self) -> Option<Rect2> {
+        None
+    }
+    /// Called when the object leaves the windows, objects that change the state of the characters release them
+    fn deactivate(&mut self) {}
+
+    /// Registers with the `ObjectManager` of the tree, called in `ready`
+    fn register(&mut self) {
+        let managed = ObjectManager::register(self.node());
+        self.set_managed(managed);
+    }
+    /// Called in `exit_tree`, the object registers again in `ready` when it is added back to a tree
+    fn unregister(&mut self) {
+        let mut node = self.node();
+        if self.is_managed() {
+            ObjectManager::unregister(node.clone());
+            self.set_managed(false);
+        }
+        node.request_ready();
+    }
+    /// Checks the characters when there is no `ObjectManager`, called in the physics process
+    fn process_unmanaged(&mut self) {
+        if self.is_managed() {
+            return;
+        }
+        let node = self.node().upcast::<Node>();
+        self.interact_players(&Character::players_in_tree(&node));
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    object: ManagedObject,
+    cells: Vec<Cell>,
+    /// Registration order, objects are always updated in this order so that collisions are deterministic
+    order: u64,
+}
+
+fn cell_of(position: Vector2) -> Cell {
+    (
+        (position.x / CELL_SIZE).floor() as i32,
+        (position.y / CELL_SIZE).floor() as i32,
+    )
+}
+
+/// Physics priority of the object manager, it runs after every object moved in its own physics process
+const PIPELINE_PRIORITY: i32 = 1000;
+
+/// Central object manager, like the object loading routine of the original games.
+/// Objects register themselves in a spatial grid, only the ones inside a window around the cameras are active
+/// and solid objects are collided with all the characters in a single pass.
+///
+/// It also drives the frame in a fixed order, like the main loop of the original games:
+/// 1. objects move (in their own physics process, which runs before the manager)
+/// 2. characters move
+/// 3. solid objects push out the characters
+/// 4. interactive objects (bumpers, zones) check the characters
+/// 5. layer switchers check the characters
+#[derive(GodotClass)]
+#[class(init, base=Node)]
+pub struct ObjectManager {
+    /// Cameras used to find the active windows, if empty the current camera and the characters are used
+    #[export]
+    cameras: Array<Gd<Camera2D>>,
+    /// Distance outside of the screen where objects are still active horizontally
+    #[export]
+    #[init(val = 128.0)]
+    horizontal_margin: f32,
+    /// Distance outside of the screen where objects are still active vertically
+    #[export]
+    #[init(val = 256.0)]
+    vertical_margin: f32,
+
+    entries: HashMap<InstanceId, Entry>,
+    grid: HashMap<Cell, Vec<InstanceId>>,
+    /// Active objects sorted by registration order
+    active: Vec<InstanceId>,
+    next_order: u64,
+    base: Base<Node>,
+}
+
+#[godot_api]
+impl INode for ObjectManager {
+    fn enter_tree(&mut self) {
+        // Joined in enter_tree so that objects can find the manager in their ready
+        self.base_mut().add_to_group("object_manager");
+        self.base_mut()
+            .set_physics_process_priority(PIPELINE_PRIORITY);
+    }
+    fn exit_tree(&mut self) {
+        // Everything goes back to updating on its own
+        let node = self.base().clone();
+        for mut character in Character::characters_in_tree(&node) {
+            character.bind_mut().set_scheduled(false);
+        }
+        for mut switcher in Self::layer_switchers(&node) {
+            switcher.bind_mut().set_scheduled(false);
+        }
+        for entry in self.entries.values_mut() {
+            entry.object.set_managed(false);
+        }
+    }
+    fn physics_process(&mut self, delta: f64) {
+        self.update_active_objects();
+        self.update_object_velocities();
+        self.move_characters(delta);
+        self.collide_objects();
+        self.interact_objects();
+        self.update_layer_switchers();
+        self.update_cells();
+    }
+}
+
+#[godot_api]
+impl ObjectManager {
+    /// Nodes that need to run after the whole frame pipeline (like cameras) can use a higher physics priority
+    #[constant]
+    const PIPELINE_PRIORITY: i32 = PIPELINE_PRIORITY;
+
+    /// Registers an object with the manager in its scene tree, returns false if there isn't one
+    #[func]
+    pub fn register(object: Gd<Node2D>) -> bool {
+        let Some(mut manager) = Self::find(&object.clone().upcast()) else {
+            return false;
+        };
+        manager.bind_mut().add(object);
+        true
+    }
+    /// Removes an object from the manager in its scene tree
+    #[func]
+    pub fn unregister(object: Gd<Node2D>) {
+        if let Some(mut manager) = Self::find(&object.clone().upcast()) {
+            manager.bind_mut().remove(object.instance_id());
+        }
+    }
+    /// How many objects are currently active
+    #[func]
+    fn active_count(&self) -> i32 {
+        i32::try_from(self.active.len()).unwrap_or(i32::MAX)
+    }
+}
+
+impl ObjectManager {
+    /// The object manager in the scene tree of `node`, if there is one
+    pub(crate) fn find(node: &Gd<Node>) -> Option<Gd<Self>> {
+        node.get_tree()?
+            .get_first_node_in_group("object_manager")?
+            .try_cast::<Self>()
+            .ok()
+    }
+
+    fn add(&mut self, mut object: Gd<Node2D>) {
+        // Objects start inactive until they are inside a window
+        object.set_process_mode(ProcessMode::DISABLED);
+        let id = object.instance_id();
+        // Objects register in their ready and can't be bound yet, large objects get all their cells in `update_cells`
+        let cell = cell_of(object.get_global_position());
+        self.grid.entry(cell).or_default().push(id);
+        self.entries.insert(
+            id,
+            Entry {
+                object: ManagedObject::from_node(object),
+                cells: vec![cell],
+                order: self.next_order,
+            },
+        );
+        self.next_order += 1;
+    }
+
+    fn remove(&mut self, id: InstanceId) {
+        if let Some(entry) = self.entries.remove(&id) {
+            // It may be deactivated, it goes back to processing on its own
+            entry.object.node().set_process_mode(ProcessMode::INHERIT);
+            for cell in entry.cells {
+                self.remove_from_cell(id, cell);
+            }
+        }
+        self.active.retain(|active_id| *active_id != id);
+    }
+
+    fn remove_from_cell(&mut self, id: InstanceId, cell: Cell) {
+        if let Some(ids) = self.grid.get_mut(&cell) {
+            ids.retain(|i| *i != id);
+            if ids.is_empty() {
+                self.grid.remove(&cell);
+            }
+        }
+    }
+
+    /// Areas of the world shown by the cameras, grown by the margins.
+    /// Without cameras set, it uses the current camera and a screen sized area around each character
+    /// so that split screen and sidekicks work without any setup
+    fn active_windows(&self) -> Vec<Rect2> {
+        let mut windows: Vec<Rect2> = self
+            .cameras
+            .iter_shared()
+            .map(|camera| Self::camera_window(&camera))
+            .collect();
+        if windows.is_empty() {
+            let node = self.base().clone();
+            if let Some(viewport) = node.get_viewport() {
+                if let Some(camera) = viewport.get_camera_2d() {
+                    windows.push(Self::camera_window(&camera));
+                }
+                let size = viewport.get_visible_rect().size;
+                for player in Character::players_in_tree(&node) {
+                    let position = player.get_global_position() - size * 0.5;
+                    windows.push(Rect2::new(position, size));
+                }
+            }
+        }
+        windows
+            .into_iter()
+            .map(|window| {
+                window.grow_individual(
+                    self.horizontal_margin,
+                    self.vertical_margin,
+                    self.horizontal_margin,
+                    self.vertical_margin,
+                )
+            })
+            .collect()
+    }
+
+    fn camera_window(camera: &Gd<Camera2D>) -> Rect2 {
+        let size = camera.get_viewport_rect().size / camera.get_zoom();
+        let position = camera.get_screen_center_position() - size * 0.5;
+        Rect2::new(position, size)
+    }
+
+    /// Activates the objects inside the windows and deactivates the ones that left them
+    fn update_active_objects(&mut self) {
+        let windows = self.active_windows();
+        if windows.is_empty() {
+            return;
+        }
+        let mut in_windows = HashSet::new();
+        for window in windows {
+            let (min_x, min_y) = cell_of(window.position);
+            let (max_x, max_y) = cell_of(window.end());
+            for x in min_x..=max_x {
+                for y in min_y..=max_y {
+                    if let Some(ids) = self.grid.get(&(x, y)) {
+                        in_windows.extend(ids.iter().copied());
+                    }
+                }
+            }
+        }
+
+        let mut active: Vec<InstanceId> = in_windows.iter().copied().collect();
+        active.sort_by_key(|id| self.entries.get(id).map_or(u64::MAX, |entry| entry.order));
+        let was_active: HashSet<InstanceId> = self.active.iter().copied().collect();
+        for id in active.iter().filter(|id| !was_active.contains(id)) {
+            if let Some(entry) = self.entries.get_mut(id) {
+                entry.object.node().set_process_mode(ProcessMode::INHERIT);
+                entry.object.reset_velocity();
+            }
+        }
+        for id in self.active.iter().filter(|id| !in_windows.contains(id)) {
+            if let Some(entry) = self.entries.get_mut(id) {
+                entry.object.node().set_process_mode(ProcessMode::DISABLED);
+                entry.object.deactivate();
+            }
+        }
+        self.active = active;
+    }
+
+    /// Objects have moved, their velocity is used by the characters standing on them
+    fn update_object_velocities(&mut self) {
+        for id in &self.active {
+            if let Some(entry) = self.entries.get_mut(id) {
+                entry.object.update_velocity();
+            }
+        }
+    }
+
+    /// Steps every character that isn't paused or disabled
+    fn move_characters(&self, delta: f64) {
+        let node = self.base().clone();
+        for mut character in Character::characters_in_tree(&node) {
+            if !character.can_process() || !character.is_physics_processing() {
+                continue;
+            }
+            let mut character = character.bind_mut();
+            character.set_scheduled(true);
+            character.physics_step(delta);
+        }
+    }
+
+    /// Collides every active solid object with every character
+    fn collide_objects(&mut self) {
+        let node = self.base().clone();
+        let players = Character::players_in_tree(&node);
+        if players.is_empty() {
+            return;
+        }
+        for id in &self.active {
+            if let Some(entry) = self.entries.get_mut(id) {
+                entry.object.collide_players(&players);
+            }
+        }
+    }
+
+    /// Lets every active interactive object check the characters, after they were pushed out of the solid objects
+    fn interact_objects(&mut self) {
+        let node = self.base().clone();
+        let players = Character::players_in_tree(&node);
+        for id in &self.active {
+            if let Some(entry) = self.entries.get_mut(id) {
+                entry.object.interact_players(&players);
+            }
+        }
+    }
+
+    fn update_layer_switchers(&self) {
+        let node = self.base().clone();
+        let players = Character::players_in_tree(&node);
+        for mut switcher in Self::layer_switchers(&node) {
+            if switcher.can_process() {
+                switcher.bind_mut().update_players(&players);
+            }
+        }
+    }
+
+    fn layer_switchers(node: &Gd<Node>) -> Vec<Gd<LayerSwitcher>> {
+        let Some(mut tree) = node.get_tree() else {
+            return vec![];
+        };
+        tree.get_nodes_in_group("layer_switcher")
+            .iter_shared()
+            .filter_map(|node| node.try_cast::<LayerSwitcher>().ok())
+            .collect()
+    }
+
+    /// Moves objects that changed position to their new cells.
+    /// Inactive objects are checked too, a parent that isn't managed (like a `PlatformMover`)
+    /// can keep moving them and they need to be found when they come back into a window
+    fn update_cells(&mut self) {
+        let mut moved = vec![];
+        for (id, entry) in &mut self.entries {
+            let cells = entry.object.cells();
+            if cells != entry.cells {
+                moved.push((
+                    *id,
+                    std::mem::replace(&mut entry.cells, cells.clone()),
+                    cells,
+                ));
+            }
+        }
+        for (id, old_cells, new_cells) in moved {
+            for cell in old_cells {
+                self.remove_from_cell(id, cell);
+            }
+            for cell in new_cells {
+                self.grid.entry(cell).or_default().push(id);
+            }
+        }
+    }
+}
@@ -0,0 +1,432 @@
+#![allow(clippy::needless_pass_by_value)]
+use std::f32::consts::TAU;
+
+use godot::{
+    classes::{Engine, Path2D},
+    prelude::*,
+};
+
+use crate::{
+    character::Character,
+    object_manager::ManagedObject,
+    solid_object::{Collision, CollisionInfo},
+};
+
+/// Frames a falling platform keeps falling before stopping
+const MAX_FALL_FRAMES: i32 = 256;
+
+#[derive(GodotConvert, Var, Export, Default, Debug, PartialEq, Eq, Clone, Copy)]
+#[godot(via = GString)]
+enum MotionType {
+    /// Follows the curve of `path`
+    Path,
+    /// Goes around the mover, `radius` is the radius on each axis
+    #[default]
+    Circle,
+    /// Swings below the mover, `radius.y` is the length of the chain
+    Pendulum,
+    /// Goes back and forth through the mover, `radius` is the amplitude on each axis
+    Sine,
+}
+
+#[derive(GodotConvert, Var, Export, Default, Debug, PartialEq, Eq, Clone, Copy)]
+#[godot(via = GString)]
+enum PlayMode {
+    /// Starts over from the beginning when reaching the end, open paths jump back to their start
+    /// and drop the riders
+    #[default]
+    Loop,
+    /// Goes backwards when reaching the end
+    PingPong,
+    /// Stops at the end
+    Once,
+}
+
+impl MotionType {
+    /// Offset of the circle, pendulum and sine motions at the given phase, `None` for paths
+    fn periodic_offset(self, phase: f32, radius: Vector2, swing_angle: f32) -> Option<Vector2> {
+        let angle = TAU * phase;
+        match self {
+            Self::Path => None,
+            Self::Circle => Some(Vector2::new(angle.cos(), angle.sin()) * radius),
+            Self::Pendulum => {
+                let swing = swing_angle * angle.sin();
+                Some(Vector2::new(swing.sin(), swing.cos()) * radius.y)
+            }
+            Self::Sine => Some(radius * angle.sin()),
+        }
+    }
+}
+
+impl PlayMode {
+    /// Brings back a phase stepped in `direction` between 0 and 1,
+    /// returns the new phase and direction, and true when the end was reached
+    fn wrap(self, phase: f32, direction: f32) -> (f32, f32, bool) {
+        match self {
+            Self::Loop => (phase.rem_euclid(1.0), direction, phase >= 1.0),
+            Self::PingPong => {
+                if phase >= 1.0 {
+                    (2.0 - phase, -1.0, true)
+                } else if phase <= 0.0 {
+                    (-phase, 1.0, false)
+                } else {
+                    (phase, direction, false)
+                }
+            }
+            Self::Once => (phase.min(1.0), direction, phase >= 1.0),
+        }
+    }
+}
+
+#[derive(GodotConvert, Var, Export, Default, Debug, PartialEq, Eq, Clone, Copy)]
+#[godot(via = GString)]
+enum Trigger {
+    /// Always moving
+    #[default]
+    Always,
+    /// Starts moving when a character stands on the platform
+    StoodOn,
+    /// Falls `fall_delay` frames after a character stands on the platform
+    Fall,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+enum MoverState {
+    #[default]
+    Waiting,
+    Moving,
+    /// Counting down before falling
+    Shaking {
+        timer: i32,
+    },
+    Falling {
+        speed: f32,
+        frames: i32,
+    },
+    Stopped,
+}
+
+/// Moves a solid object along a path, a circle, a pendulum or a sine wave, like the moving platforms of the original games.
+/// The mover is the center of the motion (the pivot for pendulums) and it moves in 60 Hz steps,
+/// one step each physics frame, before the `ObjectManager` computes the velocity used by the riders
+#[derive(GodotClass)]
+#[class(init, base=Node2D)]
+pub struct PlatformMover {
+    /// Object being moved (a `SolidObject`, `SlopedSolidObject` or any `Node2D`)
+    #[export]
+    target: Option<Gd<Node2D>>,
+    #[export]
+    motion_type: MotionType,
+    /// Curve to follow when using the `Path` motion
+    #[export]
+    path: Option<Gd<Path2D>>,
+    /// Pixels moved each frame along the path
+    #[export]
+    #[init(val = 1.0)]
+    path_speed: f32,
+    /// Frames to complete a circle, a swing or a sine wave
+    #[export(range = (1.0, 1024.0, 1.0, or_greater))]
+    #[init(val = 256)]
+    period: i32,
+    /// Radius of the circle, amplitude of the sine wave, length of the pendulum on y
+    #[export]
+    #[init(val = Vector2::new(64.0, 64.0))]
+    radius: Vector2,
+    /// Maximum angle of the pendulum from the vertical
+    #[export(range = (0.0, 180.0, 1.0, radians_as_degrees))]
+    #[init(val = std::f32::consts::FRAC_PI_4)]
+    swing_angle: f32,
+    #[export]
+    mode: PlayMode,
+    #[export]
+    trigger: Trigger,
+    /// Frames between being stood on and falling, when using the `Fall` trigger
+    #[export]
+    #[init(val = 32)]
+    fall_delay: i32,
+    #[export]
+    #[init(val = 0.21875)]
+    fall_gravity: f32,
+
+    /// Progress of the motion, from 0 to 1
+    #[var(get)]
+    phase: f32,
+    /// 1 when going forwards, -1 when going backwards in ping pong mode
+    direction: f32,
+    state: MoverState,
+    /// True on the frame the target jumped from the end of an open path back to its start
+    wrapped: bool,
+    base: Base<Node2D>,
+}
+
+#[godot_api]
+impl INode2D for PlatformMover {
+    fn ready(&mut self) {
+        if self.target.is_none() {
+            self.target = self.base().get_parent().and_then(|p| p.try_cast().ok());
+        }
+        // Move before the objects compute their velocity
+        self.base_mut().set_physics_process_priority(-1);
+        self.direction = 1.0;
+        self.state = if self.trigger == Trigger::Always {
+            MoverState::Moving
+        } else {
+            MoverState::Waiting
+        };
+        let callable = self.base().callable("on_target_collided");
+        if let Some(target) = &mut self.target {
+            if target.has_signal("collided") {
+                target.connect("collided", &callable);
+            }
+        }
+        if !Engine::singleton().is_editor_hint() {
+            if self.mode == PlayMode::Loop && self.is_open_path() {
+                godot_warn!(
+                    "{} loops on an open path, the platform jumps back to the start",
+                    self.base().get_name()
+                );
+            }
+            self.place_target();
+            if let Some(target) = self.target.clone() {
+                ManagedObject::from_node(target).reset_velocity();
+            }
+        }
+    }
+
+    fn physics_process(&mut self, _delta: f64) {
+        match self.state {
+            MoverState::Waiting | MoverState::Stopped => return,
+            MoverState::Moving => self.advance(),
+            MoverState::Shaking { timer } => {
+                self.state = if timer <= 0 {
+                    godot_print!("Platform falls");
+                    MoverState::Falling {
+                        speed: 0.0,
+                        frames: 0,
+                    }
+                } else {
+                    MoverState::Shaking { timer: timer - 1 }
+                };
+                return;
+            }
+            MoverState::Falling { speed, frames } => {
+                self.fall(speed, frames);
+                return;
+            }
+        }
+        self.place_target();
+        if self.wrapped {
+            self.wrapped = false;
+            self.release_riders();
+        }
+    }
+}
+
+#[godot_api]
+impl PlatformMover {
+    #[signal]
+    fn reached_end();
+
+    #[func]
+    fn on_target_collided(
+        &mut self,
+        collision: Collision,
+        _player: Gd<Node2D>,
+        _info: Gd<CollisionInfo>,
+    ) {
+        if collision != Collision::Up || self.state != MoverState::Waiting {
+            return;
+        }
+        self.state = match self.trigger {
+            Trigger::Always | Trigger::StoodOn => MoverState::Moving,
+            Trigger::Fall => MoverState::Shaking {
+                timer: self.fall_delay,
+            },
+        };
+    }
+    /// Starts moving, for example from a button
+    #[func]
+    fn start(&mut self) {
+        if self.state == MoverState::Waiting {
+            self.state = MoverState::Moving;
+        }
+    }
+    /// Position of the target at the given phase, relative to the mover
+    #[func]
+    fn offset_at(&self, phase: f32) -> Vector2 {
+        self.motion_type
+            .periodic_offset(phase, self.radius, self.swing_angle)
+            .unwrap_or_else(|| self.path_point(phase) - self.base().get_global_position())
+    }
+}
+
+impl PlatformMover {
+    /// Steps the phase by one frame, applying the play mode at the ends
+    fn advance(&mut self) {
+        let step = match self.motion_type {
+            MotionType::Path => {
+                let length = self.path_length();
+                if length <= 0.0 {
+                    return;
+                }
+                self.path_speed / length
+            }
+            #[allow(clippy::cast_precision_loss)]
+            _ => 1.0 / self.period.max(1) as f32,
+        };
+        let (phase, direction, reached_end) = self
+            .mode
+            .wrap(self.phase + step * self.direction, self.direction);
+        self.phase = phase;
+        self.direction = direction;
+        if !reached_end {
+            return;
+        }
+        match self.mode {
+            PlayMode::Loop => self.wrapped = self.is_open_path(),
+            PlayMode::PingPong => {}
+            PlayMode::Once => self.state = MoverState::Stopped,
+        }
+        self.base_mut().emit_signal("reached_end", &[]);
+    }
+
+    fn place_target(&mut self) {
+        let position = self.base().get_global_position() + self.offset_at(self.phase);
+        if let Some(target) = &mut self.target {
+            target.set_global_position(position);
+        }
+    }
+
+    fn fall(&mut self, speed: f32, frames: i32) {
+        let speed = speed + self.fall_gravity;
+        if let Some(target) = &mut self.target {
+            let position = target.get_global_position() + Vector2::new(0.0, speed);
+            target.set_global_position(position);
+        }
+        self.state = if frames >= MAX_FALL_FRAMES {
+            MoverState::Stopped
+        } else {
+            MoverState::Falling {
+                speed,
+                frames: frames + 1,
+            }
+        };
+    }
+
+    /// Drops the characters standing on the target and forgets its last position,
+    /// so that a jump back to the start doesn't carry them or report a huge velocity
+    fn release_riders(&self) {
+        let Some(target) = self.target.clone() else {
+            return;
+        };
+        let node = self.base().clone().upcast::<Node>();
+        for mut player in Character::players_in_tree(&node) {
+            if player.bind().get_standing_object() == Some(target.clone()) {
+                player.bind_mut().clear_standing_objects();
+            }
+        }
+        ManagedObject::from_node(target).reset_velocity();
+    }
+
+    /// True when following a path whose ends don't meet
+    fn is_open_path(&self) -> bool {
+        if self.motion_type != MotionType::Path {
+            return false;
+        }
+        let Some(curve) = self.path.as_ref().and_then(|path| path.get_curve()) else {
+            return false;
+        };
+        let count = curve.get_point_count();
+        count > 1
+            && curve
+                .get_point_position(0)
+                .distance_to(curve.get_point_position(count - 1))
+                > 0.5
+    }
+
+    fn path_length(&self) -> f32 {
+        self.path
+            .as_ref()
+            .and_then(|path| path.get_curve())
+            .map_or(0.0, |curve| curve.get_baked_length())
+    }
+
+    /// Global position on the path at the given phase
+    fn path_point(&self, phase: f32) -> Vector2 {
+        let Some(path) = &self.path else {
+            return self.base().get_global_position();
+        };
+        let Some(curve) = path.get_curve() else {
+            return path.get_global_position();
+        };
+        let point = curve
+            .sample_baked_ex()
+            .offset(phase * curve.get_baked_length())
+            .done();
+        path.get_global_transform() * point
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use godot::builtin::math::assert_eq_approx;
+    use godot::prelude::*;
+
+    use super::{MotionType, PlayMode};
+
+    #[test]
+    fn loop_wraps_to_the_start() {
+        let (phase, direction, reached_end) = PlayMode::Loop.wrap(0.5, 1.0);
+        assert_eq_approx!(phase, 0.5);
+        assert_eq_approx!(direction, 1.0);
+        assert!(!reached_end);
+        let (phase, direction, reached_end) = PlayMode::Loop.wrap(1.25, 1.0);
+        assert_eq_approx!(phase, 0.25);
+        assert_eq_approx!(direction, 1.0);
+        assert!(reached_end);
+    }
+
+    #[test]
+    fn ping_pong_bounces_at_both_ends() {
+        let (phase, direction, reached_end) = PlayMode::PingPong.wrap(1.25, 1.0);
+        assert_eq_approx!(phase, 0.75);
+        assert_eq_approx!(direction, -1.0);
+        assert!(reached_end);
+        // Going back to the start is not the end
+        let (phase, direction, reached_end) = PlayMode::PingPong.wrap(-0.25, -1.0);
+        assert_eq_approx!(phase, 0.25);
+        assert_eq_approx!(direction, 1.0);
+        assert!(!reached_end);
+    }
+
+    #[test]
+    fn once_stops_at_the_end() {
+        let (phase, _, reached_end) = PlayMode::Once.wrap(0.75, 1.0);
+        assert_eq_approx!(phase, 0.75);
+        assert!(!reached_end);
+        let (phase, _, reached_end) = PlayMode::Once.wrap(1.5, 1.0);
+        assert_eq_approx!(phase, 1.0);
+        assert!(reached_end);
+    }
+
+    #[test]
+    fn periodic_offsets_follow_the_phase() {
+        let radius = Vector2::new(32.0, 16.0);
+        let offset = |motion: MotionType, phase: f32| {
+            motion
+                .periodic_offset(phase, radius, 1.0)
+                .unwrap_or(Vector2::INF)
+        };
+        assert_eq_approx!(offset(MotionType::Circle, 0.0), Vector2::new(32.0, 0.0));
+        assert_eq_approx!(offset(MotionType::Circle, 0.25), Vector2::new(0.0, 16.0));
+        assert_eq_approx!(offset(MotionType::Sine, 0.25), radius);
+        assert_eq_approx!(offset(MotionType::Sine, 0.5), Vector2::ZERO);
+        // The pendulum hangs straight down in the middle of the swing
+        assert_eq_approx!(offset(MotionType::Pendulum, 0.0), Vector2::new(0.0, 16.0));
+        assert_eq_approx!(
+            offset(MotionType::Pendulum, 0.25),
+            Vector2::new(1.0_f32.sin(), 1.0_f32.cos()) * 16.0
+        );
+        assert!(MotionType::Path.periodic_offset(0.5, radius, 1.0).is_none());
+    }
+}
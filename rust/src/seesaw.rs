@@ -0,0 +1,274 @@
+#![allow(clippy::needless_pass_by_value)]
+use godot::{
+    classes::{CollisionPolygon2D, Engine},
+    prelude::*,
+};
+
+use crate::{
+    character::Character,
+    solid_object::{sloped_solid_object::SlopedSolidObject, Collision, CollisionInfo},
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Tilt {
+    /// Left end down
+    Left,
+    #[default]
+    Flat,
+    /// Right end down
+    Right,
+}
+
+impl Tilt {
+    /// -1 when the left end is down, 1 when the right end is down
+    const fn sign(self) -> f32 {
+        match self {
+            Self::Left => -1.0,
+            Self::Flat => 0.0,
+            Self::Right => 1.0,
+        }
+    }
+    const fn from_side(right: bool) -> Self {
+        if right {
+            Self::Right
+        } else {
+            Self::Left
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum WeightState {
+    Resting,
+    /// Launched straight up from its end
+    Flying {
+        speed: f32,
+    },
+}
+
+/// Seesaw from Star Light Zone (Sonic 1) and Hill Top Zone (Sonic 2).
+/// The `sloped_solid_object` polygon is swapped between three tilts, it tilts towards the characters standing on it
+/// or towards the weight. Landing on the raised end launches the weight, and when the weight falls back
+/// on the raised end the characters on the other end are launched with the fall speed of the weight
+#[derive(GodotClass)]
+#[class(init, base=Node2D)]
+pub struct Seesaw {
+    #[export]
+    sloped_solid_object: Option<Gd<SlopedSolidObject>>,
+    /// Collision polygon of the sloped solid object, its points are replaced when tilting
+    #[export]
+    collision_polygon: Option<Gd<CollisionPolygon2D>>,
+    /// Rotated with the tilt
+    #[export]
+    sprite: Option<Gd<Node2D>>,
+    /// Ball or spiked weight sitting on one end, optional
+    #[export]
+    weight: Option<Gd<Node2D>>,
+    /// Set to true to start with the weight on the right end
+    #[export]
+    weight_on_right: bool,
+    #[export]
+    #[init(val = 48.0)]
+    width_radius: f32,
+    #[export]
+    #[init(val = 8.0)]
+    thickness: f32,
+    /// How much each end goes up or down when tilting
+    #[export]
+    #[init(val = 12.0)]
+    tilt_height: f32,
+    #[export]
+    #[init(val = 12.0)]
+    weight_radius: f32,
+    #[export]
+    #[init(val = 0.21875)]
+    weight_gravity: f32,
+    /// Minimum speed of the weight when a character lands on the raised end
+    #[export]
+    #[init(val = 4.0)]
+    min_launch_speed: f32,
+
+    tilt: Tilt,
+    weight_state: Option<WeightState>,
+    /// Highest falling speed of a character landing on the seesaw this frame
+    landing_speed: f32,
+    base: Base<Node2D>,
+}
+
+#[godot_api]
+impl INode2D for Seesaw {
+    fn ready(&mut self) {
+        if Engine::singleton().is_editor_hint() {
+            return;
+        }
+        let callable = self.base().callable("on_collided");
+        if let Some(object) = &mut self.sloped_solid_object {
+            object.connect("collided", &callable);
+        }
+        self.weight_state = self.weight.as_ref().map(|_| WeightState::Resting);
+        let tilt = if self.weight.is_some() {
+            Tilt::from_side(self.weight_on_right)
+        } else {
+            Tilt::Flat
+        };
+        self.set_tilt(tilt);
+        self.place_weight();
+    }
+
+    fn physics_process(&mut self, _delta: f64) {
+        let tilt = self.rider_side().map_or_else(
+            || {
+                if self.weight.is_some() {
+                    Tilt::from_side(self.weight_on_right)
+                } else {
+                    Tilt::Flat
+                }
+            },
+            Tilt::from_side,
+        );
+        if tilt != self.tilt {
+            // The end with the weight went up
+            if tilt != Tilt::Flat
+                && tilt != Tilt::from_side(self.weight_on_right)
+                && self.weight_state == Some(WeightState::Resting)
+            {
+                let speed = self.landing_speed.max(self.min_launch_speed);
+                godot_print!("Seesaw launches weight at {speed}");
+                self.weight_state = Some(WeightState::Flying { speed: -speed });
+            }
+            self.set_tilt(tilt);
+        }
+        self.landing_speed = 0.0;
+        self.update_weight();
+    }
+}
+
+#[godot_api]
+impl Seesaw {
+    #[func]
+    fn on_collided(
+        &mut self,
+        collision: Collision,
+        _player: Gd<Character>,
+        info: Gd<CollisionInfo>,
+    ) {
+        if collision != Collision::Up {
+            return;
+        }
+        let speed = info.bind().get_relative_velocity().y;
+        self.landing_speed = self.landing_speed.max(speed);
+    }
+}
+
+impl Seesaw {
+    /// Side of the characters standing on the seesaw, true for right, none if no one is standing near an end
+    fn rider_side(&self) -> Option<bool> {
+        let object = self.sloped_solid_object.clone()?.upcast::<Node2D>();
+        let center = self.base().get_global_position().x;
+        let node = self.base().clone().upcast::<Node>();
+        Character::players_in_tree(&node)
+            .into_iter()
+            .filter(|player| player.bind().get_standing_object() == Some(object.clone()))
+            .map(|player| player.get_global_position().x - center)
+            .filter(|dx| dx.abs() > 8.0)
+            .map(|dx| dx > 0.0)
+            .last()
+    }
+
+    fn set_tilt(&mut self, tilt: Tilt) {
+        self.tilt = tilt;
+        let (w, t) = (self.width_radius, self.thickness);
+        let h = self.tilt_height * tilt.sign();
+        // Godot y points down, so the end that is down has the highest y
+        let polygon = PackedVector2Array::from(&[
+            Vector2::new(-w, -t - h),
+            Vector2::new(w, -t + h),
+            Vector2::new(w, h),
+            Vector2::new(-w, -h),
+        ]);
+        if let Some(collision_polygon) = &mut self.collision_polygon {
+            collision_polygon.set_polygon(&polygon);
+        }
+        let angle = (h / w).atan();
+        if let Some(sprite) = &mut self.sprite {
+            sprite.set_rotation(angle);
+        }
+    }
+
+    /// Local position of the weight resting on its end
+    fn weight_rest_position(&self) -> Vector2 {
+        let side = if self.weight_on_right { 1.0 } else { -1.0 };
+        let x = (self.width_radius - self.weight_radius) * side;
+        let h = self.tilt_height * self.tilt.sign();
+        let top = -self.thickness + h * x / self.width_radius;
+        Vector2::new(x, top - self.weight_radius)
+    }
+
+    fn place_weight(&mut self) {
+        let position = self.weight_rest_position();
+        if let Some(weight) = &mut self.weight {
+            weight.set_position(position);
+        }
+    }
+
+    fn update_weight(&mut self) {
+        let Some(WeightState::Flying { speed }) = self.weight_state else {
+            self.place_weight();
+            return;
+        };
+        let Some(mut weight) = self.weight.clone() else {
+            return;
+        };
+        let speed = speed + self.weight_gravity;
+        let mut position = weight.get_position();
+        position.y += speed;
+        let rest = self.weight_rest_position();
+        if speed > 0.0 && position.y >= rest.y {
+            self.weight_state = Some(WeightState::Resting);
+            let weight_tilt = Tilt::from_side(self.weight_on_right);
+            if self.tilt != weight_tilt {
+                // Falling on the raised end, launches whoever is on the other end
+                self.set_tilt(weight_tilt);
+                self.launch_riders(speed);
+            }
+            self.place_weight();
+        } else {
+            weight.set_position(position);
+            self.weight_state = Some(WeightState::Flying { speed });
+        }
+    }
+
+    fn launch_riders(&self, speed: f32) {
+        let Some(object) = self.sloped_solid_object.clone() else {
+            return;
+        };
+        let object = object.upcast::<Node2D>();
+        let node = self.base().clone().upcast::<Node>();
+        for mut player in Character::players_in_tree(&node) {
+            if player.bind().get_standing_object() == Some(object.clone()) {
+                godot_print!("Seesaw launches character at {speed}");
+                player.bind_mut().launch_upwards(speed);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use godot::builtin::math::assert_eq_approx;
+
+    use super::Tilt;
+
+    #[test]
+    fn tilt_sign_follows_the_lowered_end() {
+        assert_eq_approx!(Tilt::Left.sign(), -1.0);
+        assert_eq_approx!(Tilt::Flat.sign(), 0.0);
+        assert_eq_approx!(Tilt::Right.sign(), 1.0);
+    }
+
+    #[test]
+    fn tilt_from_side() {
+        assert_eq!(Tilt::from_side(true), Tilt::Right);
+        assert_eq!(Tilt::from_side(false), Tilt::Left);
+    }
+}
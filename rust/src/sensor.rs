@@ -1,7 +1,11 @@
 use std::f32::consts::FRAC_PI_2;
 
 use godot::{
-    classes::{Engine, PhysicsRayQueryParameters2D, ThemeDb, TileData, TileMap},
+    classes::{
+        CollisionObject2D, CollisionPolygon2D, CollisionShape2D, Engine,
+        PhysicsRayQueryParameters2D, PhysicsServer2D, ThemeDb, TileData, TileMap, TileMapLayer,
+        TileSet,
+    },
     prelude::*,
 };
 
@@ -119,6 +123,93 @@ pub struct DetectionResult {
     pub angle: f32,
     pub solidity: Solidity,
     pub snap: bool,
+    pub surface: SurfaceProperties,
+}
+
+/// Properties of the detected surface, read from the custom data layers of the `TileSet` with the same names.
+/// Layers missing from the `TileSet` keep the default value
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SurfaceProperties {
+    /// Multiplies acceleration, deceleration and friction, lower than 1 for ice, 0 in the `TileSet` is the same as 1
+    pub friction: f32,
+    /// The character doesn't slip or fall on steep slopes
+    pub sticky: bool,
+    /// Upwards speed the character is launched with when landing, 0 to land normally
+    pub bounce: f32,
+    /// Hurts the character standing on it, like lava
+    pub hurt: bool,
+    /// Pixels per frame the surface moves the characters standing on it, for conveyor belts
+    pub surface_velocity: f32,
+    /// Physics layer the character switches to when standing on it, 0 to keep the current one
+    pub collision_layer: u32,
+}
+
+impl Default for SurfaceProperties {
+    fn default() -> Self {
+        Self {
+            friction: 1.0,
+            sticky: false,
+            bounce: 0.0,
+            hurt: false,
+            surface_velocity: 0.0,
+            collision_layer: 0,
+        }
+    }
+}
+
+impl SurfaceProperties {
+    fn from_tile_data(tile_data: &Gd<TileData>, tile_set: Option<&Gd<TileSet>>) -> Self {
+        let default = Self::default();
+        let get = |name: &str| {
+            tile_set
+                .filter(|tile_set| tile_set.get_custom_data_layer_by_name(name) >= 0)
+                .map(|_| tile_data.get_custom_data(name))
+        };
+        Self {
+            // Tiles without a value have 0, which keeps the normal friction
+            friction: get("friction")
+                .and_then(|v| v.try_to().ok())
+                .filter(|friction| *friction > 0.0)
+                .unwrap_or(default.friction),
+            sticky: get("sticky").is_some_and(|v| v.booleanize()),
+            bounce: get("bounce")
+                .and_then(|v| v.try_to().ok())
+                .unwrap_or(default.bounce),
+            hurt: get("hurt").is_some_and(|v| v.booleanize()),
+            surface_velocity: get("surface_velocity")
+                .and_then(|v| v.try_to().ok())
+                .unwrap_or(default.surface_velocity),
+            collision_layer: get("collision_layer")
+                .and_then(|v| v.try_to().ok())
+                .unwrap_or(default.collision_layer),
+        }
+    }
+
+    fn to_dictionary(self) -> Dictionary {
+        dict! {"friction":self.friction,"sticky":self.sticky,"bounce":self.bounce,"hurt":self.hurt,
+        "surface_velocity":self.surface_velocity,"collision_layer":self.collision_layer}
+    }
+
+    fn from_dictionary(dict: &Dictionary) -> Self {
+        let default = Self::default();
+        let get = |name: &str| dict.get(name);
+        Self {
+            friction: get("friction")
+                .and_then(|v| v.try_to().ok())
+                .unwrap_or(default.friction),
+            sticky: get("sticky").is_some_and(|v| v.booleanize()),
+            bounce: get("bounce")
+                .and_then(|v| v.try_to().ok())
+                .unwrap_or(default.bounce),
+            hurt: get("hurt").is_some_and(|v| v.booleanize()),
+            surface_velocity: get("surface_velocity")
+                .and_then(|v| v.try_to().ok())
+                .unwrap_or(default.surface_velocity),
+            collision_layer: get("collision_layer")
+                .and_then(|v| v.try_to().ok())
+                .unwrap_or(default.collision_layer),
+        }
+    }
 }
 
 impl GodotConvert for DetectionResult {
@@ -130,7 +221,9 @@ impl ToGodot for DetectionResult {
         Self: 'v;
 
     fn to_godot(&self) -> Self::Via {
-        dict! {"distance":self.distance,"angle":self.angle,"solidity":self.solidity,"snap":self.snap}
+        let mut dict = dict! {"distance":self.distance,"angle":self.angle,"solidity":self.solidity,"snap":self.snap};
+        dict.extend_dictionary(&self.surface.to_dictionary(), false);
+        dict
     }
 }
 impl FromGodot for DetectionResult {
@@ -151,22 +244,31 @@ impl FromGodot for DetectionResult {
             .get("snap")
             .ok_or_else(ConvertError::default)?
             .try_to()?;
+        let surface = SurfaceProperties::from_dictionary(&dict);
         Ok(Self {
             distance,
             angle,
             solidity,
             snap,
+            surface,
         })
     }
 }
 
 impl DetectionResult {
-    const fn new(distance: f32, angle: f32, solidity: Solidity, snap: bool) -> Self {
+    const fn new(
+        distance: f32,
+        angle: f32,
+        solidity: Solidity,
+        snap: bool,
+        surface: SurfaceProperties,
+    ) -> Self {
         Self {
             distance,
             angle,
             solidity,
             snap,
+            surface,
         }
     }
 }
@@ -320,26 +422,37 @@ impl Sensor {
         self.last_collision_point = Some(collision_point);
         let distance = self.get_distance(collision_point);
         let normal = result.normal;
-        let (solidity, snapped) = if let Some((layer, tile_data)) = get_collided_tile_data(result) {
-            let polygon_full = if tile_data.get_collision_polygons_count(layer) > 0 {
-                let collision_data = tile_data.get_collision_polygon_points(layer, 0);
-                is_polygon_full(&collision_data)
-            } else {
-                false
-            };
-            // Checking for flagged tiles: https://info.sonicretro.org/SPG:Solid_Tiles#Flagged_Tiles
-            let snapped = polygon_full || tile_data.get_custom_data("snap").booleanize();
-            let solidity = if tile_data.get_collision_polygons_count(layer) > 0
-                && tile_data.is_collision_polygon_one_way(layer, 0)
-            {
-                Solidity::Top
+        let (solidity, snapped, surface) =
+            if let Some((layer, tile_data, tile_set)) = get_collided_tile_data(result) {
+                let polygon_full = if tile_data.get_collision_polygons_count(layer) > 0 {
+                    let collision_data = tile_data.get_collision_polygon_points(layer, 0);
+                    is_polygon_full(&collision_data)
+                } else {
+                    false
+                };
+                // Checking for flagged tiles: https://info.sonicretro.org/SPG:Solid_Tiles#Flagged_Tiles
+                let snapped = polygon_full
+                    || tile_set.as_ref().is_some_and(|tile_set| {
+                        tile_set.get_custom_data_layer_by_name("snap") >= 0
+                            && tile_data.get_custom_data("snap").booleanize()
+                    });
+                let solidity = if tile_data.get_collision_polygons_count(layer) > 0
+                    && tile_data.is_collision_polygon_one_way(layer, 0)
+                {
+                    Solidity::Top
+                } else {
+                    Solidity::Fully
+                };
+                let surface = SurfaceProperties::from_tile_data(&tile_data, tile_set.as_ref());
+                (solidity, snapped, surface)
             } else {
-                Solidity::Fully
+                let solidity = if is_one_way_shape(result) {
+                    Solidity::Top
+                } else {
+                    Solidity::Fully
+                };
+                (solidity, false, SurfaceProperties::default())
             };
-            (solidity, snapped)
-        } else {
-            (Solidity::Fully, false)
-        };
         let angle = normal.plane_angle();
 
         DetectionResult::new(
@@ -347,6 +460,7 @@ impl Sensor {
             angle,
             solidity,
             normal == Vector2::ZERO || snapped,
+            surface,
         )
     }
     /// Absolute distance from current global position to the collision point
@@ -374,12 +488,58 @@ impl Sensor {
     }
 }
 
-/// If the `RaycastResult` has collided with a physics body attached to a `TileMap` , it will return the `TileData` for it
-fn get_collided_tile_data(raycast_result: &RaycastResult) -> Option<(i32, Gd<TileData>)> {
+/// If the `RaycastResult` has collided with a physics body attached to a `TileMap` or a `TileMapLayer`,
+/// it will return the physics layer and the `TileData` for it
+fn get_collided_tile_data(
+    raycast_result: &RaycastResult,
+) -> Option<(i32, Gd<TileData>, Option<Gd<TileSet>>)> {
     let collider_rid = raycast_result.rid;
-    let mut tilemap = raycast_result.collider.clone().try_cast::<TileMap>().ok()?;
-    let map_coords = tilemap.get_coords_for_body_rid(collider_rid);
-    let layer = tilemap.get_layer_for_body_rid(collider_rid);
-    let tile_data = tilemap.get_cell_tile_data(layer, map_coords)?;
-    Some((layer, tile_data))
+    let (tile_data, tile_set) = match raycast_result.collider.clone().try_cast::<TileMap>() {
+        Ok(mut tilemap) => {
+            let map_coords = tilemap.get_coords_for_body_rid(collider_rid);
+            let layer = tilemap.get_layer_for_body_rid(collider_rid);
+            (
+                tilemap.get_cell_tile_data(layer, map_coords)?,
+                tilemap.get_tileset(),
+            )
+        }
+        Err(collider) => {
+            let layer = collider.try_cast::<TileMapLayer>().ok()?;
+            let map_coords = layer.get_coords_for_body_rid(collider_rid);
+            (layer.get_cell_tile_data(map_coords)?, layer.get_tile_set())
+        }
+    };
+    let physics_layer = tile_set
+        .as_ref()
+        .map_or(0, |tile_set| physics_layer_of_body(tile_set, collider_rid));
+    Some((physics_layer, tile_data, tile_set))
+}
+
+/// Physics layer of `tile_set` that the tile body `body` was made for, tile maps make one body for each physics layer
+/// with its collision layer. Physics layers sharing the same collision layer can't be told apart, the first one is used
+fn physics_layer_of_body(tile_set: &Gd<TileSet>, body: Rid) -> i32 {
+    let collision_layer = PhysicsServer2D::singleton().body_get_collision_layer(body);
+    (0..tile_set.get_physics_layers_count())
+        .find(|layer| tile_set.get_physics_layer_collision_layer(*layer) == collision_layer)
+        .unwrap_or(0)
+}
+
+/// True when the `RaycastResult` has collided with a one way shape that is not part of a `TileMap`, like a `SolidPath2D`
+fn is_one_way_shape(raycast_result: &RaycastResult) -> bool {
+    let Ok(object) = raycast_result
+        .collider
+        .clone()
+        .try_cast::<CollisionObject2D>()
+    else {
+        return false;
+    };
+    let owner = object.shape_find_owner(raycast_result.shape);
+    object.shape_owner_get_owner(owner).is_some_and(|node| {
+        match node.try_cast::<CollisionPolygon2D>() {
+            Ok(polygon) => polygon.is_one_way_collision_enabled(),
+            Err(node) => node
+                .try_cast::<CollisionShape2D>()
+                .is_ok_and(|shape| shape.is_one_way_collision_enabled()),
+        }
+    })
 }
@@ -0,0 +1,320 @@
+use std::collections::VecDeque;
+
+use godot::{classes::object::ConnectFlags, prelude::*};
+
+use crate::character::{godot_api::State, input::CharacterInput, Character};
+
+/// How many pixels outside of the screen the sidekick can be before counting as off-screen
+const OFFSCREEN_MARGIN: f32 = 32.0;
+/// Frames between despawning and starting to fly back in
+const RESPAWN_DELAY: i32 = 64;
+/// Frame of the spindash sequence where the dash is released
+const SPINDASH_RELEASE: i32 = 32;
+
+/// Leader data recorded every frame, replayed by the sidekick with a delay
+#[derive(Debug, Clone, Copy)]
+struct LeaderRecord {
+    position: Vector2,
+    input: CharacterInput,
+    state: State,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum SidekickState {
+    #[default]
+    Following,
+    /// Spindashing to get unstuck, the timer counts the frames since it started
+    Spindash { timer: i32 },
+    /// Removed from the stage, waiting to fly back in
+    Despawned { timer: i32 },
+    /// Flying towards the leader ignoring any collision
+    FlyingIn,
+}
+
+/// CPU controller that makes a `Character` follow another one, like Tails in Sonic 2 and 3&K.
+/// Based on the sidekick routines of Sonic 2 (`TailsCPU_Normal`, `TailsCPU_Respawn` and `TailsCPU_Flying`)
+#[derive(GodotClass)]
+#[class(init, base=Node)]
+pub struct Sidekick {
+    /// Character that is being followed
+    #[export]
+    leader: Option<Gd<Character>>,
+    /// Character controlled by the CPU
+    #[export]
+    character: Option<Gd<Character>>,
+    /// How many frames the sidekick lags behind the leader
+    #[export(range = (1.0, 64.0, 1.0))]
+    #[init(val = 16)]
+    delay: i32,
+    /// Frames the sidekick can stay off-screen before despawning
+    #[export]
+    #[init(val = 300)]
+    despawn_time: i32,
+    /// Frames the sidekick has to be stuck before trying to jump or spindash
+    #[export]
+    #[init(val = 64)]
+    stuck_time: i32,
+    /// Distance above the leader from where the sidekick flies back in
+    #[export]
+    #[init(val = 192.0)]
+    respawn_height: f32,
+
+    history: VecDeque<LeaderRecord>,
+    state: SidekickState,
+    frame_counter: i32,
+    offscreen_timer: i32,
+    stuck_timer: i32,
+    /// Holds jump for the whole catch-up jump so it reaches full height
+    catch_up_jump: bool,
+    base: Base<Node>,
+}
+
+#[godot_api]
+impl INode for Sidekick {
+    fn ready(&mut self) {
+        // Run before the characters so that the input is ready when they move
+        self.base_mut().set_physics_process_priority(-1);
+        let callable = self.base().callable("on_character_died");
+        if let Some(character) = &mut self.character {
+            {
+                let mut character = character.bind_mut();
+                character.set_cpu_controlled(true);
+                character.set_reload_on_death(false);
+            }
+            // Deferred because the character is still borrowed when it dies
+            #[allow(clippy::cast_sign_loss)]
+            character
+                .connect_ex("died", &callable)
+                .flags(ConnectFlags::DEFERRED.ord() as u32)
+                .done();
+        }
+    }
+
+    fn physics_process(&mut self, _delta: f64) {
+        let (Some(leader), Some(mut character)) = (self.leader.clone(), self.character.clone())
+        else {
+            return;
+        };
+        self.frame_counter = self.frame_counter.wrapping_add(1);
+        self.record(&leader);
+
+        match self.state {
+            SidekickState::Following => {
+                self.follow(&mut character);
+                self.check_offscreen(&mut character);
+            }
+            SidekickState::Spindash { timer } => self.spindash(&mut character, timer),
+            SidekickState::Despawned { timer } => self.wait_respawn(&leader, &mut character, timer),
+            SidekickState::FlyingIn => self.fly_in(&leader, &mut character),
+        }
+    }
+}
+
+#[godot_api]
+impl Sidekick {
+    #[func]
+    fn on_character_died(&mut self) {
+        if let Some(mut character) = self.character.clone() {
+            self.despawn(&mut character);
+        }
+    }
+}
+
+impl Sidekick {
+    /// Stores the leader position and input, dropping anything older than `delay`
+    fn record(&mut self, leader: &Gd<Character>) {
+        let record = {
+            let leader_ref = leader.bind();
+            LeaderRecord {
+                position: leader.get_global_position(),
+                input: leader_ref.input(),
+                state: leader_ref.state,
+            }
+        };
+        self.history.push_back(record);
+        let max_len = usize::try_from(self.delay.max(1)).unwrap_or(1) + 1;
+        while self.history.len() > max_len {
+            self.history.pop_front();
+        }
+    }
+
+    /// Where the leader was `delay` frames ago
+    fn target(&self) -> Option<LeaderRecord> {
+        self.history.front().copied()
+    }
+
+    /// Replays the delayed leader input, correcting it when the sidekick drifts away
+    fn follow(&mut self, character: &mut Gd<Character>) {
+        let Some(target) = self.target() else {
+            return;
+        };
+        let position = character.get_global_position();
+        let (is_grounded, ground_speed, state) = {
+            let character = character.bind();
+            (
+                character.get_is_grounded(),
+                character.get_ground_speed(),
+                character.state,
+            )
+        };
+        let dx = target.position.x - position.x;
+        let dy = target.position.y - position.y;
+        let mut input = target.input;
+
+        // Walk towards where the leader was instead of blindly copying its input
+        if dx.abs() >= 16.0 {
+            input.left = dx < 0.0;
+            input.right = dx > 0.0;
+        }
+        // Stop so that the sidekick can crouch and spindash together with the leader
+        if target.state.is_spindashing() && !state.is_spindashing() {
+            input.left = false;
+            input.right = false;
+        }
+
+        if is_grounded {
+            self.catch_up_jump = false;
+            // Catch-up jump when the leader is well above, only checked every 64 frames
+            if self.frame_counter % 64 == 0 && dy <= -32.0 && !state.is_crouching() {
+                Self::press_jump(&mut input);
+                self.catch_up_jump = true;
+            }
+        } else if self.catch_up_jump {
+            input.jump = true;
+        }
+
+        // Stuck against a wall or an object while the leader moves away
+        let is_stuck = is_grounded && ground_speed.abs() < 1.0 && dx.abs() >= 64.0;
+        if is_stuck {
+            self.stuck_timer += 1;
+        } else {
+            self.stuck_timer = 0;
+        }
+        if self.stuck_timer >= self.stuck_time {
+            godot_print!("Sidekick stuck");
+            self.stuck_timer = 0;
+            if dy <= -32.0 {
+                Self::press_jump(&mut input);
+                self.catch_up_jump = true;
+            } else {
+                self.state = SidekickState::Spindash { timer: 0 };
+            }
+        }
+
+        character.bind_mut().set_cpu_input(input);
+    }
+
+    /// Faces the leader, crouches, revs a few times and releases
+    fn spindash(&mut self, character: &mut Gd<Character>, timer: i32) {
+        let dx = self.target().map_or(0.0, |target| {
+            target.position.x - character.get_global_position().x
+        });
+        let mut input = CharacterInput::default();
+        if timer == 0 {
+            input.left = dx < 0.0;
+            input.right = dx > 0.0;
+        } else if timer < SPINDASH_RELEASE {
+            input.roll = true;
+            if timer % 8 == 0 {
+                Self::press_jump(&mut input);
+            }
+        }
+        self.state = if timer >= SPINDASH_RELEASE {
+            SidekickState::Following
+        } else {
+            SidekickState::Spindash { timer: timer + 1 }
+        };
+        character.bind_mut().set_cpu_input(input);
+    }
+
+    fn check_offscreen(&mut self, character: &mut Gd<Character>) {
+        if Self::is_on_screen(character) {
+            self.offscreen_timer = 0;
+            return;
+        }
+        self.offscreen_timer += 1;
+        if self.offscreen_timer >= self.despawn_time {
+            self.despawn(character);
+        }
+    }
+
+    fn is_on_screen(character: &Gd<Character>) -> bool {
+        let Some(viewport) = character.get_viewport() else {
+            return true;
+        };
+        let position = viewport.get_canvas_transform() * character.get_global_position();
+        viewport
+            .get_visible_rect()
+            .grow(OFFSCREEN_MARGIN)
+            .has_point(position)
+    }
+
+    fn despawn(&mut self, character: &mut Gd<Character>) {
+        godot_print!("Sidekick despawned");
+        character.hide();
+        character.set_physics_process(false);
+        {
+            let mut character = character.bind_mut();
+            character.clear_standing_objects();
+            character.set_object_collision(false);
+            character.set_velocity(Vector2::ZERO);
+            character.set_ground_speed(0.0);
+            character.set_cpu_input(CharacterInput::default());
+        }
+        self.offscreen_timer = 0;
+        self.stuck_timer = 0;
+        self.catch_up_jump = false;
+        self.state = SidekickState::Despawned {
+            timer: RESPAWN_DELAY,
+        };
+    }
+
+    /// Waits a bit and for the leader to be on the ground before flying back in from above
+    fn wait_respawn(&mut self, leader: &Gd<Character>, character: &mut Gd<Character>, timer: i32) {
+        if timer > 0 {
+            self.state = SidekickState::Despawned { timer: timer - 1 };
+            return;
+        }
+        if !leader.bind().get_is_grounded() || self.frame_counter % 64 != 0 {
+            return;
+        }
+        let mut position = leader.get_global_position();
+        position.y -= self.respawn_height;
+        character.set_global_position(position);
+        character.show();
+        self.state = SidekickState::FlyingIn;
+    }
+
+    /// Moves straight to where the leader was, ignoring collision, until reaching it
+    fn fly_in(&mut self, leader: &Gd<Character>, character: &mut Gd<Character>) {
+        let Some(target) = self.target() else {
+            return;
+        };
+        let mut position = character.get_global_position();
+        let dx = target.position.x - position.x;
+        let dy = target.position.y - position.y;
+
+        let x_step = (leader.bind().get_velocity().x.abs() + 1.0).min(dx.abs());
+        position.x += x_step * dx.signum();
+        position.y += dy.abs().min(1.0) * dy.signum();
+        character.set_global_position(position);
+        if dx != 0.0 {
+            character.bind_mut().set_flip_h(dx < 0.0);
+        }
+
+        if position.distance_to(target.position) < 1.0 {
+            godot_print!("Sidekick landed");
+            character.set_physics_process(true);
+            let mut character = character.bind_mut();
+            character.set_object_collision(true);
+            character.set_grounded(false);
+            character.set_state(State::Idle);
+            self.state = SidekickState::Following;
+        }
+    }
+
+    const fn press_jump(input: &mut CharacterInput) {
+        input.jump = true;
+        input.jump_just_pressed = true;
+    }
+}
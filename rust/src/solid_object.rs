@@ -1,3 +1,5 @@
+pub mod hazard_object;
+pub mod pushable_block;
 pub mod sloped_solid_object;
 
 use godot::{
@@ -9,6 +11,7 @@ use godot::{
 
 use crate::{
     character::{godot_api::State, Character},
+    object_manager::ObjectManager,
     sensor::TILE_SIZE,
 };
 /// From: <https://info.sonicretro.org/SPG:Solid_Objects>
@@ -35,10 +38,10 @@ pub struct SolidObject {
     /// Debug collision shape, is only for display
     #[export]
     collision_shape: Option<Gd<CollisionShape2D>>,
-    /// Used for moving platforms
-    #[var]
-    velocity: Vector2,
-    position_last_frame: Vector2,
+    /// Pixels per frame the characters standing on it are moved, for conveyor belts
+    #[export]
+    surface_velocity: f32,
+    solid: SolidState,
     base: Base<Area2D>,
 }
 
@@ -48,109 +51,368 @@ impl IArea2D for SolidObject {
         self.physics_process(_delta);
     }
     fn ready(&mut self) {
-        let base = self.base().clone();
-        self.base_mut()
-            .connect("collided", &base.callable("on_collided"));
+        self.register();
+    }
+    fn exit_tree(&mut self) {
+        self.unregister();
     }
 }
 
 #[godot_api]
 impl SolidObject {
     #[signal]
-    fn collided(collision: Collision, player: Gd<Character>);
-
-    #[func]
-    fn on_collided(&self, collision: Collision, mut player: Gd<Character>) {
-        if collision == Collision::Up {
-            player
-                .bind_mut()
-                .set_stand_on_object(self.base().clone().cast::<Self>());
-        }
-        // TODO: make pushing so that player knows what it is pushing
-        if (collision == Collision::Left || collision == Collision::Right)
-            && player.bind().state != State::Pushing
-            && player.bind().get_is_grounded()
-        {
-            player.bind_mut().set_state(State::Pushing);
+    fn collided(collision: Collision, player: Gd<Character>, info: Gd<CollisionInfo>);
+
+    /// Called before emitting `collided`, so that scripts connected to it already see the character standing or pushing
+    fn on_collided(&self, collision: Collision, player: &mut Gd<Character>) {
+        let object = self.base().clone();
+        match collision {
+            Collision::Up => player.bind_mut().set_stand_on_object(object.cast::<Self>()),
+            Collision::Left => player.bind_mut().start_pushing(object.upcast(), 1),
+            Collision::Right => player.bind_mut().start_pushing(object.upcast(), -1),
+            Collision::Down => {}
         }
     }
-    fn emit_collided(&mut self, collision: Collision, player: &Gd<Character>) {
-        self.base_mut()
-            .emit_signal("collided", &[collision.to_variant(), player.to_variant()]);
+    fn emit_collided(&mut self, collision: Collision, player: &Gd<Character>, contact: Contact) {
+        self.on_collided(collision, &mut player.clone());
+        let info = contact.info(collision, player, self.solid.velocity);
+        self.base_mut().emit_signal(
+            "collided",
+            &[
+                collision.to_variant(),
+                player.to_variant(),
+                info.to_variant(),
+            ],
+        );
     }
     /// Collision code, separated into its own function so that it can be called in subclasses of `SolidObject`
     #[func]
     fn physics_process(&mut self, _delta: f64) {
-        let Some(mut player) = self
-            .base()
-            .get_tree()
-            .and_then(|mut tree| tree.get_first_node_in_group(c"player"))
-            .and_then(|player| player.try_cast::<Character>().ok())
-        else {
-            return;
-        };
-
-        let position = self.collision_shape_global_position();
-        let radius = Vector2::new(self.width_radius, self.height_radius);
-        if self.is_monitor {
-            // Don't collide with the monitor if player is attacking (it will collide with the monitor hitbox)
-            if !player.bind().get_attacking() {
-                if let Some(collision) = item_monitor_collision(&mut player, position, radius) {
-                    self.emit_collided(collision, &player);
-                }
-            }
-        } else if let Some(collision) =
-            solid_object_collision(&mut player, position, radius, self.top_solid_only)
-        {
-            self.emit_collided(collision, &player);
-        }
-
-        let position = self.base().get_global_position();
-        self.velocity = position - self.position_last_frame;
-        self.position_last_frame = position;
+        self.process_unmanaged();
     }
+    /// Used for moving platforms
     #[func]
-    fn set_width_radius(&mut self, value: f32) {
+    #[allow(clippy::missing_const_for_fn)]
+    fn get_velocity(&self) -> Vector2 {
+        self.solid.velocity
+    }
+    #[func]
+    pub fn set_width_radius(&mut self, value: f32) {
         self.width_radius = value;
         self.update_shape();
     }
     #[func]
-    fn set_height_radius(&mut self, value: f32) {
+    pub fn set_height_radius(&mut self, value: f32) {
         self.height_radius = value;
         self.update_shape();
     }
 }
 
 impl SolidObject {
-    pub fn collision_shape_global_position(&self) -> Vector2 {
-        if let Some(collision_shape) = &self.collision_shape {
-            return collision_shape.get_global_position();
+    /// Moves the collision box horizontally in global space, used to resize the object from one side.
+    /// Without a collision shape the offset is kept in the solid state
+    pub fn shift_collision_shape(&mut self, offset: f32) {
+        let position = self.collision_shape_global_position() + Vector2::new(offset, 0.0);
+        if let Some(collision_shape) = &mut self.collision_shape {
+            collision_shape.set_global_position(position);
+        } else {
+            self.solid.center_offset = self.base().to_local(position);
+        }
+    }
+}
+
+impl SolidBody for SolidObject {
+    fn node(&self) -> Gd<Area2D> {
+        self.base().clone()
+    }
+    fn solid_state(&self) -> &SolidState {
+        &self.solid
+    }
+    fn solid_state_mut(&mut self) -> &mut SolidState {
+        &mut self.solid
+    }
+    fn collide_players(&mut self, players: &[Gd<Character>]) {
+        let position = self.collision_shape_global_position();
+        let radius = self.global_radius();
+        for player in players {
+            let mut player = player.clone();
+            let contact = Contact::new(&player);
+            if self.is_monitor {
+                // Don't collide with the monitor if player is attacking (it will collide with the monitor hitbox)
+                if !player.bind().get_attacking() {
+                    if let Some(collision) = item_monitor_collision(&mut player, position, radius) {
+                        self.emit_collided(collision, &player, contact);
+                    }
+                }
+            } else if let Some(collision) =
+                solid_object_collision(&mut player, position, radius, self.top_solid_only)
+            {
+                self.emit_collided(collision, &player, contact);
+            }
         }
-        self.base().get_global_position()
+    }
+    fn standing_surface(&self, _position: Vector2) -> (Vector2, f32, f32) {
+        self.box_surface()
+    }
+    fn surface_velocity(&self) -> f32 {
+        self.surface_velocity
+    }
+}
+
+impl SolidBox for SolidObject {
+    fn radius(&self) -> Vector2 {
+        Vector2::new(self.width_radius, self.height_radius)
+    }
+    fn collision_shape(&self) -> Option<&Gd<CollisionShape2D>> {
+        self.collision_shape.as_ref()
+    }
+}
+
+/// Motion of a solid object and whether an `ObjectManager` updates it, each solid object class keeps one
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SolidState {
+    /// How much the object moved during the last frame, used for moving platforms
+    pub velocity: Vector2,
+    motion: FrameMotion,
+    /// True when registered with an `ObjectManager`, which takes care of the collision
+    managed: bool,
+    /// Center of the box relative to the node, used when there is no collision shape
+    center_offset: Vector2,
+}
+
+/// Behaviour shared by the solid objects, the `ObjectManager` and the characters standing on them use it
+pub trait SolidBody {
+    fn node(&self) -> Gd<Area2D>;
+    fn solid_state(&self) -> &SolidState;
+    fn solid_state_mut(&mut self) -> &mut SolidState;
+    /// Checks the collision with every character, done by the `ObjectManager` when the object is registered
+    fn collide_players(&mut self, players: &[Gd<Character>]);
+    /// Center, width radius and top of the object under `position`, for the characters standing on it
+    fn standing_surface(&self, position: Vector2) -> (Vector2, f32, f32);
+    /// Pixels per frame the characters standing on it are moved, for conveyor belts
+    fn surface_velocity(&self) -> f32 {
+        0.0
+    }
+
+    /// Registers with the `ObjectManager` of the tree, called in `ready`
+    fn register(&mut self) {
+        let managed = ObjectManager::register(self.node().upcast());
+        self.set_managed(managed);
+        self.reset_velocity();
+    }
+    /// Called in `exit_tree`, the object registers again in `ready` when it is added back to a tree
+    fn unregister(&mut self) {
+        let mut node = self.node();
+        if self.solid_state().managed {
+            ObjectManager::unregister(node.clone().upcast());
+            self.set_managed(false);
+        }
+        node.request_ready();
+    }
+    fn set_managed(&mut self, value: bool) {
+        self.solid_state_mut().managed = value;
+    }
+    /// Updates the velocity and collides with the characters when there is no `ObjectManager`,
+    /// called in the physics process after the object moved
+    fn process_unmanaged(&mut self) {
+        if self.solid_state().managed {
+            return;
+        }
+        self.update_velocity();
+        let node = self.node().upcast::<Node>();
+        self.collide_players(&Character::players_in_tree(&node));
+    }
+    /// Velocity is how much the object moved this frame, must be called after the object moves
+    fn update_velocity(&mut self) {
+        let transform = self.node().get_global_transform();
+        let state = self.solid_state_mut();
+        state.velocity = state.motion.update(transform);
+    }
+    /// Forgets the last position, so that an object that was inactive doesn't get a huge velocity
+    fn reset_velocity(&mut self) {
+        let transform = self.node().get_global_transform();
+        let state = self.solid_state_mut();
+        state.motion.reset(transform);
+        state.velocity = Vector2::ZERO;
+    }
+    /// How the object moved during the last frame
+    fn motion(&self) -> FrameMotion {
+        self.solid_state().motion
+    }
+}
+
+/// Solid objects colliding as a box, with a debug collision shape
+pub trait SolidBox: SolidBody {
+    /// Width and height radius, before the transform of the object
+    fn radius(&self) -> Vector2;
+    fn collision_shape(&self) -> Option<&Gd<CollisionShape2D>>;
+
+    /// Radius of the collision box, taking into account the rotation and scale of the object
+    fn global_radius(&self) -> Vector2 {
+        let transform = self.collision_shape().map_or_else(
+            || self.node().get_global_transform(),
+            |shape| shape.get_global_transform(),
+        );
+        transformed_radius(transform, self.radius())
+    }
+    fn collision_shape_global_position(&self) -> Vector2 {
+        self.collision_shape().map_or_else(
+            || self.node().to_global(self.solid_state().center_offset),
+            |shape| shape.get_global_position(),
+        )
+    }
+    /// Center, width radius and top of the box
+    fn box_surface(&self) -> (Vector2, f32, f32) {
+        let position = self.collision_shape_global_position();
+        let radius = self.global_radius();
+        (position, radius.x, position.y - radius.y)
     }
     /// Updates debug collision shape
     fn update_shape(&self) {
         if let Some(mut rect) = self
-            .collision_shape
-            .as_deref()
-            .and_then(CollisionShape2D::get_shape)
+            .collision_shape()
+            .and_then(|shape| shape.get_shape())
             .and_then(|shape| shape.try_cast::<RectangleShape2D>().ok())
         {
-            rect.set_size(Vector2::new(
-                self.width_radius * 2.0,
-                self.height_radius * 2.0,
-            ));
+            rect.set_size(self.radius() * 2.0);
+        }
+    }
+}
+
+/// How an object moved during the last frame, including rotation and scale,
+/// used to carry the characters standing on it
+#[derive(Debug, Clone, Copy)]
+pub struct FrameMotion {
+    transform_last_frame: Transform2D,
+    delta: Transform2D,
+}
+
+impl Default for FrameMotion {
+    fn default() -> Self {
+        Self {
+            transform_last_frame: Transform2D::IDENTITY,
+            delta: Transform2D::IDENTITY,
         }
     }
 }
 
-#[derive(GodotConvert, Var, Export, Debug, PartialEq, Eq, Clone, Copy)]
-#[godot(via = GString)]
-enum Collision {
-    Left,
-    Right,
-    Up,
-    Down,
+impl FrameMotion {
+    /// Stores the new transform of the object, returns the linear velocity
+    pub fn update(&mut self, transform: Transform2D) -> Vector2 {
+        let velocity = transform.origin - self.transform_last_frame.origin;
+        self.delta = transform * self.transform_last_frame.affine_inverse();
+        self.transform_last_frame = transform;
+        velocity
+    }
+    pub const fn reset(&mut self, transform: Transform2D) {
+        self.transform_last_frame = transform;
+        self.delta = Transform2D::IDENTITY;
+    }
+    /// Where a point attached to the object ended up after the last frame
+    pub fn carry(&self, point: Vector2) -> Vector2 {
+        self.delta * point
+    }
+}
+
+/// Radius of the bounding box of a rectangle after being rotated and scaled by `transform`,
+/// objects always collide as axis aligned boxes like in the original games
+fn transformed_radius(transform: Transform2D, radius: Vector2) -> Vector2 {
+    let a = transform.a * radius.x;
+    let b = transform.b * radius.y;
+    Vector2::new(a.x.abs() + b.x.abs(), a.y.abs() + b.y.abs())
+}
+
+/// Side of the object touched by the character, the values are the constants of `CollisionInfo`
+#[derive(GodotConvert, Var, Export, Default, Debug, PartialEq, Eq, Clone, Copy)]
+#[godot(via = i32)]
+pub enum Collision {
+    Left = 0,
+    Right = 1,
+    #[default]
+    Up = 2,
+    Down = 3,
+}
+
+impl Collision {
+    /// Normal of the touched side, pointing from the object towards the character
+    pub const fn normal(self) -> Vector2 {
+        match self {
+            Self::Left => Vector2::LEFT,
+            Self::Right => Vector2::RIGHT,
+            Self::Up => Vector2::UP,
+            Self::Down => Vector2::DOWN,
+        }
+    }
+}
+
+/// Details of a collision between a character and an object, sent with the `collided` signal
+#[derive(GodotClass, Debug)]
+#[class(init, base=RefCounted)]
+pub struct CollisionInfo {
+    #[var]
+    collision: Collision,
+    /// Normal of the touched side, pointing from the object towards the character
+    #[var]
+    normal: Vector2,
+    /// Distance the character was pushed out of the object
+    #[var]
+    penetration: f32,
+    /// Velocity of the character relative to the object, before the collision changed it
+    #[var]
+    relative_velocity: Vector2,
+    /// State of the character before the collision, landing on the object changes it
+    #[var]
+    state: State,
+}
+
+#[godot_api]
+impl CollisionInfo {
+    #[constant]
+    const LEFT: i32 = Collision::Left as i32;
+    #[constant]
+    const RIGHT: i32 = Collision::Right as i32;
+    #[constant]
+    const UP: i32 = Collision::Up as i32;
+    #[constant]
+    const DOWN: i32 = Collision::Down as i32;
+}
+
+/// The character before colliding with an object, to build the `CollisionInfo` afterwards
+#[derive(Debug, Clone, Copy)]
+struct Contact {
+    position: Vector2,
+    velocity: Vector2,
+    state: State,
+}
+
+impl Contact {
+    fn new(player: &Gd<Character>) -> Self {
+        let bind = player.bind();
+        Self {
+            position: player.get_global_position(),
+            velocity: bind.get_velocity(),
+            state: bind.state,
+        }
+    }
+    fn info(
+        self,
+        collision: Collision,
+        player: &Gd<Character>,
+        object_velocity: Vector2,
+    ) -> Gd<CollisionInfo> {
+        let normal = collision.normal();
+        Gd::from_object(CollisionInfo {
+            collision,
+            normal,
+            penetration: self.penetration(player.get_global_position(), normal),
+            relative_velocity: self.velocity - object_velocity,
+            state: self.state,
+        })
+    }
+    /// How far the character was pushed out along `normal` to end up at `position`
+    fn penetration(self, position: Vector2, normal: Vector2) -> f32 {
+        (position - self.position).dot(normal).max(0.0)
+    }
 }
 
 fn solid_object_collision(
@@ -425,3 +687,40 @@ fn item_monitor_collision(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use godot::builtin::math::assert_eq_approx;
+    use godot::prelude::*;
+
+    use crate::character::godot_api::State;
+
+    use super::{Collision, Contact};
+
+    fn contact(position: Vector2) -> Contact {
+        Contact {
+            position,
+            velocity: Vector2::ZERO,
+            state: State::Idle,
+        }
+    }
+
+    #[test]
+    fn penetration_is_the_push_along_the_normal() {
+        let contact = contact(Vector2::new(10.0, 20.0));
+        let normal = Collision::Up.normal();
+        assert_eq_approx!(contact.penetration(Vector2::new(10.0, 17.0), normal), 3.0);
+        // Sliding along the side doesn't count
+        assert_eq_approx!(contact.penetration(Vector2::new(14.0, 17.0), normal), 3.0);
+        let normal = Collision::Left.normal();
+        assert_eq_approx!(contact.penetration(Vector2::new(6.0, 20.0), normal), 4.0);
+    }
+
+    #[test]
+    fn penetration_is_never_negative() {
+        let contact = contact(Vector2::ZERO);
+        let normal = Collision::Down.normal();
+        assert_eq_approx!(contact.penetration(Vector2::new(0.0, -5.0), normal), 0.0);
+        assert_eq_approx!(contact.penetration(Vector2::ZERO, normal), 0.0);
+    }
+}
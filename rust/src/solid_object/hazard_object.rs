@@ -0,0 +1,276 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use godot::{
+    classes::{Area2D, CollisionShape2D, Engine, IArea2D},
+    obj::WithBaseField,
+    prelude::*,
+};
+
+use crate::{character::Character, tool::Direction};
+
+use super::{
+    solid_object_collision, Collision, CollisionInfo, Contact, SolidBody, SolidBox, SolidState,
+};
+
+/// Sides of the object in `hurt_sides`
+const SIDE_UP: u32 = 1;
+const SIDE_RIGHT: u32 = 2;
+const SIDE_DOWN: u32 = 4;
+const SIDE_LEFT: u32 = 8;
+
+const fn side_of_direction(direction: Direction) -> u32 {
+    match direction {
+        Direction::Up => SIDE_UP,
+        Direction::Right => SIDE_RIGHT,
+        Direction::Down => SIDE_DOWN,
+        Direction::Left => SIDE_LEFT,
+    }
+}
+
+const fn side_of_collision(collision: Collision) -> u32 {
+    match collision {
+        Collision::Up => SIDE_UP,
+        Collision::Right => SIDE_RIGHT,
+        Collision::Down => SIDE_DOWN,
+        Collision::Left => SIDE_LEFT,
+    }
+}
+
+/// From: <https://info.sonicretro.org/SPG:Game_Objects#Spikes>
+/// Solid object that hurts or kills the characters touching the sides in `hurt_sides`, like spikes.
+/// It can extend and retract in the pointing direction like the moving spikes of the original games
+#[derive(GodotClass)]
+#[class(tool, init, base=Area2D)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct HazardObject {
+    #[export(range = (0.0,100.0, 1.0))]
+    #[var(get, set = set_width_radius)]
+    #[init(val = 16.0)]
+    width_radius: f32,
+    #[export(range = (0.0,100.0, 1.0))]
+    #[var(get, set = set_height_radius)]
+    #[init(val = 16.0)]
+    height_radius: f32,
+    /// Where the hazard points, sets `hurt_sides` and rotates the sprite
+    #[export]
+    #[var(get, set = set_direction)]
+    direction: Direction,
+    /// Sides that hurt the characters touching them
+    #[export(flags = (Up = 1, Right = 2, Down = 4, Left = 8))]
+    #[init(val = SIDE_UP)]
+    hurt_sides: u32,
+    /// Kills the character instead of hurting it, even when it has rings
+    #[export]
+    instant_kill: bool,
+    /// Sonic 1 quirk: hurts the character even during the invulnerability after getting hit
+    #[export]
+    hurt_through_invulnerability: bool,
+    /// Extends and retracts in the pointing direction
+    #[export]
+    moving: bool,
+    /// Frames between extending and retracting
+    #[export]
+    #[init(val = 64)]
+    move_interval: i32,
+    /// Pixels moved when extending
+    #[export]
+    #[init(val = 32.0)]
+    move_distance: f32,
+    /// Pixels moved each frame while extending or retracting
+    #[export]
+    #[init(val = 8.0)]
+    move_speed: f32,
+    #[export]
+    sprite: Option<Gd<Node2D>>,
+    /// Debug collision shape, is only for display
+    #[export]
+    collision_shape: Option<Gd<CollisionShape2D>>,
+    solid: SolidState,
+    timer: i32,
+    extended: bool,
+    /// Distance moved from the retracted position
+    offset: f32,
+    base: Base<Area2D>,
+}
+
+#[godot_api]
+impl IArea2D for HazardObject {
+    fn ready(&mut self) {
+        self.update_sprite();
+        if Engine::singleton().is_editor_hint() {
+            return;
+        }
+        self.register();
+    }
+    fn exit_tree(&mut self) {
+        if !Engine::singleton().is_editor_hint() {
+            self.unregister();
+        }
+    }
+    fn physics_process(&mut self, _delta: f64) {
+        if Engine::singleton().is_editor_hint() {
+            return;
+        }
+        if self.moving {
+            self.timer += 1;
+            if self.timer >= self.move_interval {
+                self.step();
+            }
+        }
+        self.process_unmanaged();
+    }
+}
+
+#[godot_api]
+impl HazardObject {
+    #[signal]
+    fn collided(collision: Collision, player: Gd<Character>, info: Gd<CollisionInfo>);
+    /// Emitted after hurting a character, even if it had no rings and died
+    #[signal]
+    fn hurt_player(player: Gd<Character>);
+    /// Emitted after killing a character with an instant kill hazard
+    #[signal]
+    fn killed_player(player: Gd<Character>);
+
+    /// Used by the characters standing on the hazard
+    #[func]
+    #[allow(clippy::missing_const_for_fn)]
+    fn get_velocity(&self) -> Vector2 {
+        self.solid.velocity
+    }
+    #[func]
+    fn set_width_radius(&mut self, value: f32) {
+        self.width_radius = value;
+        self.update_shape();
+    }
+    #[func]
+    fn set_height_radius(&mut self, value: f32) {
+        self.height_radius = value;
+        self.update_shape();
+    }
+    #[func]
+    fn set_direction(&mut self, value: Direction) {
+        self.direction = value;
+        self.hurt_sides = side_of_direction(value);
+        self.update_sprite();
+    }
+    /// Unit vector where the hazard points
+    #[func]
+    #[allow(clippy::missing_const_for_fn)]
+    fn direction_vector(&self) -> Vector2 {
+        match self.direction {
+            Direction::Up => Vector2::UP,
+            Direction::Right => Vector2::RIGHT,
+            Direction::Down => Vector2::DOWN,
+            Direction::Left => Vector2::LEFT,
+        }
+    }
+}
+
+impl SolidBody for HazardObject {
+    fn node(&self) -> Gd<Area2D> {
+        self.base().clone()
+    }
+    fn solid_state(&self) -> &SolidState {
+        &self.solid
+    }
+    fn solid_state_mut(&mut self) -> &mut SolidState {
+        &mut self.solid
+    }
+    fn collide_players(&mut self, players: &[Gd<Character>]) {
+        let position = self.collision_shape_global_position();
+        let radius = self.global_radius();
+        for player in players {
+            let mut player = player.clone();
+            let contact = Contact::new(&player);
+            let Some(collision) = solid_object_collision(&mut player, position, radius, false)
+            else {
+                continue;
+            };
+            let object = self.base().clone();
+            match collision {
+                Collision::Up => player.bind_mut().set_stand_on_hazard_object(object.cast()),
+                Collision::Left => player.bind_mut().start_pushing(object.upcast(), 1),
+                Collision::Right => player.bind_mut().start_pushing(object.upcast(), -1),
+                Collision::Down => {}
+            }
+            if self.hurt_sides & side_of_collision(collision) != 0 {
+                self.hurt(&mut player);
+            }
+            let info = contact.info(collision, &player, self.solid.velocity);
+            self.base_mut().emit_signal(
+                "collided",
+                &[
+                    collision.to_variant(),
+                    player.to_variant(),
+                    info.to_variant(),
+                ],
+            );
+        }
+    }
+    fn standing_surface(&self, _position: Vector2) -> (Vector2, f32, f32) {
+        self.box_surface()
+    }
+}
+
+impl SolidBox for HazardObject {
+    fn radius(&self) -> Vector2 {
+        Vector2::new(self.width_radius, self.height_radius)
+    }
+    fn collision_shape(&self) -> Option<&Gd<CollisionShape2D>> {
+        self.collision_shape.as_ref()
+    }
+}
+
+impl HazardObject {
+    fn hurt(&mut self, player: &mut Gd<Character>) {
+        if self.instant_kill {
+            godot_print!("Killed by hazard");
+            player.bind().die();
+            self.base_mut()
+                .emit_signal("killed_player", &[player.to_variant()]);
+            return;
+        }
+        let hazard_x = self.collision_shape_global_position().x;
+        let hurt = if self.hurt_through_invulnerability {
+            player.bind_mut().hurt_ignoring_invulnerability(hazard_x)
+        } else {
+            player.bind_mut().hurt_from(hazard_x)
+        };
+        if hurt {
+            self.base_mut()
+                .emit_signal("hurt_player", &[player.to_variant()]);
+        }
+    }
+
+    /// Extends or retracts by `move_speed`, waiting `move_interval` frames at each end
+    fn step(&mut self) {
+        let direction = self.direction_vector();
+        let target = if self.extended {
+            0.0
+        } else {
+            self.move_distance
+        };
+        let step = (target - self.offset).clamp(-self.move_speed, self.move_speed);
+        self.offset += step;
+        let position = self.base().get_position() + direction * step;
+        self.base_mut().set_position(position);
+        if (self.offset - target).abs() < 0.5 {
+            self.offset = target;
+            self.timer = 0;
+            self.extended = !self.extended;
+        }
+    }
+
+    fn update_sprite(&mut self) {
+        let rotation = match self.direction {
+            Direction::Up => 0.0,
+            Direction::Right => FRAC_PI_2,
+            Direction::Down => PI,
+            Direction::Left => -FRAC_PI_2,
+        };
+        if let Some(sprite) = &mut self.sprite {
+            sprite.set_rotation(rotation);
+        }
+    }
+}
@@ -0,0 +1,282 @@
+use godot::{
+    classes::{Area2D, CollisionShape2D, IArea2D},
+    obj::WithBaseField,
+    prelude::*,
+};
+
+use crate::{character::Character, sensor::Sensor};
+
+use super::{
+    solid_object_collision, Collision, CollisionInfo, Contact, SolidBody, SolidBox, SolidState,
+};
+
+/// Distance the block slides over a ledge before falling
+const SLIDE_OFF_DISTANCE: f32 = 16.0;
+/// Top falling speed, the same as the characters
+const TOP_FALL_SPEED: f32 = 16.0;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+enum BlockState {
+    #[default]
+    Resting,
+    /// Sliding over a ledge, `direction` is 1 when sliding right and -1 when sliding left
+    SlidingOff {
+        direction: f32,
+        distance: f32,
+    },
+    Falling {
+        speed: f32,
+    },
+}
+
+/// From: <https://info.sonicretro.org/SPG:Game_Objects#Pushable_Blocks>
+/// Solid object that can be pushed by the characters one pixel per frame, stops against walls,
+/// slides off ledges and falls until it lands on the floor
+#[derive(GodotClass)]
+#[class(init, base=Area2D)]
+pub struct PushableBlock {
+    /// Width radius of the block
+    #[export(range = (0.0,100.0, 1.0))]
+    #[var(get, set = set_width_radius)]
+    #[init(val = 16.0)]
+    width_radius: f32,
+    /// Height radius of the block
+    #[export(range = (0.0,100.0, 1.0))]
+    #[var(get, set = set_height_radius)]
+    #[init(val = 16.0)]
+    height_radius: f32,
+    /// Pixels the block moves each frame while being pushed
+    #[export]
+    #[init(val = 1.0)]
+    push_speed: f32,
+    /// Pixels the block moves each frame while sliding off a ledge
+    #[export]
+    #[init(val = 4.0)]
+    slide_off_speed: f32,
+    /// Gravity while falling
+    #[export]
+    #[init(val = 0.21875)]
+    fall_gravity: f32,
+    /// Sensor pointing down, at the bottom center of the block
+    #[export]
+    floor_sensor: Option<Gd<Sensor>>,
+    /// Sensor pointing left, at the left edge of the block
+    #[export]
+    wall_sensor_left: Option<Gd<Sensor>>,
+    /// Sensor pointing right, at the right edge of the block
+    #[export]
+    wall_sensor_right: Option<Gd<Sensor>>,
+    /// Debug collision shape, is only for display
+    #[export]
+    collision_shape: Option<Gd<CollisionShape2D>>,
+    solid: SolidState,
+    state: BlockState,
+    base: Base<Area2D>,
+}
+
+#[godot_api]
+impl IArea2D for PushableBlock {
+    fn ready(&mut self) {
+        if self.floor_sensor.is_none() {
+            godot_warn!(
+                "{} has no floor sensor, it will not fall",
+                self.base().get_name()
+            );
+        }
+        self.register();
+    }
+    fn exit_tree(&mut self) {
+        self.unregister();
+    }
+    fn physics_process(&mut self, _delta: f64) {
+        match self.state {
+            BlockState::Resting => self.check_floor(),
+            BlockState::SlidingOff {
+                direction,
+                distance,
+            } => self.slide_off(direction, distance),
+            BlockState::Falling { speed } => self.fall(speed),
+        }
+        self.process_unmanaged();
+    }
+}
+
+#[godot_api]
+impl PushableBlock {
+    #[signal]
+    fn collided(collision: Collision, player: Gd<Character>, info: Gd<CollisionInfo>);
+    /// Emitted when the block starts falling after sliding off a ledge
+    #[signal]
+    fn started_falling();
+    #[signal]
+    fn landed();
+
+    /// Used by the characters standing on the block
+    #[func]
+    #[allow(clippy::missing_const_for_fn)]
+    fn get_velocity(&self) -> Vector2 {
+        self.solid.velocity
+    }
+    #[func]
+    fn is_falling(&self) -> bool {
+        self.state != BlockState::Resting
+    }
+    #[func]
+    fn set_width_radius(&mut self, value: f32) {
+        self.width_radius = value;
+        self.update_shape();
+    }
+    #[func]
+    fn set_height_radius(&mut self, value: f32) {
+        self.height_radius = value;
+        self.update_shape();
+    }
+}
+
+impl SolidBody for PushableBlock {
+    fn node(&self) -> Gd<Area2D> {
+        self.base().clone()
+    }
+    fn solid_state(&self) -> &SolidState {
+        &self.solid
+    }
+    fn solid_state_mut(&mut self) -> &mut SolidState {
+        &mut self.solid
+    }
+    fn collide_players(&mut self, players: &[Gd<Character>]) {
+        let radius = self.global_radius();
+        for player in players {
+            let mut player = player.clone();
+            let contact = Contact::new(&player);
+            let position = self.collision_shape_global_position();
+            let Some(collision) = solid_object_collision(&mut player, position, radius, false)
+            else {
+                continue;
+            };
+            let object = self.base().clone();
+            match collision {
+                Collision::Up => player.bind_mut().set_stand_on_pushable_block(object.cast()),
+                Collision::Left => self.pushed_by(&mut player, 1),
+                Collision::Right => self.pushed_by(&mut player, -1),
+                Collision::Down => {}
+            }
+            let info = contact.info(collision, &player, self.solid.velocity);
+            self.base_mut().emit_signal(
+                "collided",
+                &[
+                    collision.to_variant(),
+                    player.to_variant(),
+                    info.to_variant(),
+                ],
+            );
+        }
+    }
+    fn standing_surface(&self, _position: Vector2) -> (Vector2, f32, f32) {
+        self.box_surface()
+    }
+}
+
+impl SolidBox for PushableBlock {
+    fn radius(&self) -> Vector2 {
+        Vector2::new(self.width_radius, self.height_radius)
+    }
+    fn collision_shape(&self) -> Option<&Gd<CollisionShape2D>> {
+        self.collision_shape.as_ref()
+    }
+}
+
+impl PushableBlock {
+    /// The character moves the block only while pushing it and the block is resting on the floor
+    fn pushed_by(&mut self, player: &mut Gd<Character>, direction: i32) {
+        let object = self.base().clone().upcast::<Node2D>();
+        player.bind_mut().start_pushing(object.clone(), direction);
+        if self.state != BlockState::Resting || player.bind().get_pushed_object() != Some(object) {
+            return;
+        }
+        #[allow(clippy::cast_precision_loss)]
+        let direction = direction as f32;
+        if self.is_blocked(direction) {
+            return;
+        }
+        self.move_by(Vector2::new(self.push_speed * direction, 0.0));
+        player.bind_mut().push_object(self.push_speed);
+
+        // The center of the block went over the ledge
+        if self.floor_distance() > 0.0 {
+            godot_print!("Pushable block slides off");
+            player.bind_mut().end_pushing();
+            self.state = BlockState::SlidingOff {
+                direction,
+                distance: 0.0,
+            };
+        }
+    }
+
+    /// True when there is a wall closer than a push step
+    fn is_blocked(&self, direction: f32) -> bool {
+        let sensor = if direction > 0.0 {
+            &self.wall_sensor_right
+        } else {
+            &self.wall_sensor_left
+        };
+        sensor
+            .clone()
+            .and_then(|mut sensor| sensor.bind_mut().sense())
+            .is_some_and(|result| result.distance < self.push_speed)
+    }
+
+    /// Distance to the floor, positive when the block is above it and infinite when there is no floor.
+    /// A block without floor sensor is always resting on the floor
+    fn floor_distance(&self) -> f32 {
+        let Some(mut sensor) = self.floor_sensor.clone() else {
+            return 0.0;
+        };
+        let result = sensor.bind_mut().sense();
+        result.map_or(f32::INFINITY, |result| result.distance)
+    }
+
+    /// Snaps to the floor, starts falling if there isn't one
+    fn check_floor(&mut self) {
+        let distance = self.floor_distance();
+        if distance > 0.0 {
+            self.state = BlockState::Falling { speed: 0.0 };
+            self.base_mut().emit_signal("started_falling", &[]);
+        } else {
+            self.move_by(Vector2::new(0.0, distance));
+        }
+    }
+
+    fn slide_off(&mut self, direction: f32, distance: f32) {
+        self.move_by(Vector2::new(self.slide_off_speed * direction, 0.0));
+        let distance = distance + self.slide_off_speed;
+        self.state = if distance >= SLIDE_OFF_DISTANCE {
+            self.base_mut().emit_signal("started_falling", &[]);
+            BlockState::Falling { speed: 0.0 }
+        } else {
+            BlockState::SlidingOff {
+                direction,
+                distance,
+            }
+        };
+    }
+
+    fn fall(&mut self, speed: f32) {
+        let speed = (speed + self.fall_gravity).min(TOP_FALL_SPEED);
+        // Checked before moving so that the block lands instead of going through the floor
+        let distance = self.floor_distance();
+        if distance <= speed {
+            godot_print!("Pushable block landed");
+            self.move_by(Vector2::new(0.0, distance));
+            self.state = BlockState::Resting;
+            self.base_mut().emit_signal("landed", &[]);
+        } else {
+            self.move_by(Vector2::new(0.0, speed));
+            self.state = BlockState::Falling { speed };
+        }
+    }
+
+    fn move_by(&mut self, offset: Vector2) {
+        let position = self.base().get_global_position() + offset;
+        self.base_mut().set_global_position(position);
+    }
+}
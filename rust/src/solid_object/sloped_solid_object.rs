@@ -8,7 +8,7 @@ use godot::{
 
 use crate::character::Character;
 
-use super::{solid_object_collision, Collision};
+use super::{solid_object_collision, Collision, CollisionInfo, Contact, SolidBody, SolidState};
 /// From: <https://info.sonicretro.org/SPG:Solid_Objects#Sloped_Objects>
 /// In the original games, sloped objects were represented as an array, here we use a collision polygon
 #[derive(GodotClass)]
@@ -20,10 +20,10 @@ pub struct SlopedSolidObject {
     /// Shape is defined by the collision polygon
     #[export]
     collision_polygon: Option<Gd<CollisionPolygon2D>>,
-    /// Used for moving platforms
-    #[var]
-    velocity: Vector2,
-    position_last_frame: Vector2,
+    /// Pixels per frame the characters standing on it are moved, for conveyor belts
+    #[export]
+    surface_velocity: f32,
+    solid: SolidState,
     base: Base<Area2D>,
 }
 
@@ -32,29 +32,38 @@ impl IArea2D for SlopedSolidObject {
     fn physics_process(&mut self, delta: f64) {
         self.physics_process(delta);
     }
+    fn ready(&mut self) {
+        self.register();
+    }
+    fn exit_tree(&mut self) {
+        self.unregister();
+    }
 }
 
 #[godot_api]
 impl SlopedSolidObject {
     #[signal]
-    fn collided(collision: Collision, player: Gd<Character>);
-    fn emit_collided(&mut self, collision: Collision, player: &Gd<Character>) {
-        self.base_mut()
-            .emit_signal("collided", &[collision.to_variant(), player.to_variant()]);
+    fn collided(collision: Collision, player: Gd<Character>, info: Gd<CollisionInfo>);
+    fn emit_collided(&mut self, collision: Collision, player: &Gd<Character>, contact: Contact) {
+        let info = contact.info(collision, player, self.solid.velocity);
+        self.base_mut().emit_signal(
+            "collided",
+            &[
+                collision.to_variant(),
+                player.to_variant(),
+                info.to_variant(),
+            ],
+        );
     }
     #[func]
     fn physics_process(&mut self, _delta: f64) {
-        if let Some(player) = self
-            .base()
-            .get_tree()
-            .and_then(|mut tree| tree.get_first_node_in_group("player"))
-            .and_then(|player| player.try_cast::<Character>().ok())
-        {
-            self.sloped_solid_object_collision(player);
-        }
-        let position = self.base().get_global_position();
-        self.velocity = position - self.position_last_frame;
-        self.position_last_frame = position;
+        self.process_unmanaged();
+    }
+    /// Used for moving platforms
+    #[func]
+    #[allow(clippy::missing_const_for_fn)]
+    fn get_velocity(&self) -> Vector2 {
+        self.solid.velocity
     }
     /// Flips collision polygon on the x axis , used when changing spring direction
     #[func]
@@ -82,9 +91,34 @@ impl SlopedSolidObject {
     }
 }
 
+impl SolidBody for SlopedSolidObject {
+    fn node(&self) -> Gd<Area2D> {
+        self.base().clone()
+    }
+    fn solid_state(&self) -> &SolidState {
+        &self.solid
+    }
+    fn solid_state_mut(&mut self) -> &mut SolidState {
+        &mut self.solid
+    }
+    fn collide_players(&mut self, players: &[Gd<Character>]) {
+        for player in players {
+            self.sloped_solid_object_collision(player.clone());
+        }
+    }
+    fn standing_surface(&self, position: Vector2) -> (Vector2, f32, f32) {
+        let (top, _) = self.current_top_bottom(position);
+        (self.global_center(), self.width_radius(), top)
+    }
+    fn surface_velocity(&self) -> f32 {
+        self.surface_velocity
+    }
+}
+
 impl SlopedSolidObject {
     /// Collision code
     pub(super) fn sloped_solid_object_collision(&mut self, mut player: Gd<Character>) {
+        let contact = Contact::new(&player);
         let player_position = player.get_global_position();
         let (top, bottom) = self.current_top_bottom(player_position);
 
@@ -92,99 +126,49 @@ impl SlopedSolidObject {
         position.y = (bottom + top) * 0.5;
         let radius = Vector2::new(self.width_radius(), (bottom - top) * 0.5);
 
+        // Jump through platforms only collide from the top, crushing is handled by the fully solid collision
         if let Some(collision) =
             solid_object_collision(&mut player, position, radius, self.top_solid_only)
         {
-            if collision == Collision::Up {
-                player
+            let object = self.base().clone();
+            match collision {
+                Collision::Up => player
                     .bind_mut()
-                    .set_stand_on_sloped_object(self.base().clone().cast::<Self>());
+                    .set_stand_on_sloped_object(object.cast::<Self>()),
+                Collision::Left => player.bind_mut().start_pushing(object.upcast(), 1),
+                Collision::Right => player.bind_mut().start_pushing(object.upcast(), -1),
+                Collision::Down => {}
             }
-            self.emit_collided(collision, &player);
+            self.emit_collided(collision, &player, contact);
         }
     }
 
-    /// Center relative to the collision polygon
+    /// Center of the collision polygon bounds
     pub fn global_center(&self) -> Vector2 {
-        if let Some(collision_polygon) = &self.collision_polygon {
-            return collision_polygon.get_global_position() + self.polygon_center();
+        if self.collision_polygon.is_none() {
+            return self.base().get_global_position();
         }
-        self.base().get_global_position() + self.polygon_center()
+        let (min_x, max_x) = self.min_max_x();
+        let (min_y, max_y) = self.min_max_y();
+        Vector2::new((min_x + max_x) * 0.5, (min_y + max_y) * 0.5)
     }
 
-    /// Calculates the y position of the collision polygon at player.x for both the bottom and top of the polygon
+    /// Calculates the y position of the collision polygon at player.x for both the bottom and top of the polygon.
+    /// From <https://info.sonicretro.org/SPG:Solid_Objects#Sloped_Objects>, the object is then collided like a normal
+    /// solid object with that height, so the top contour is used for landing, the bottom one for ceilings and both for the sides
     pub fn current_top_bottom(&self, player_position: Vector2) -> (f32, f32) {
-        let Some(collision_polygon) = &self.collision_polygon else {
+        let polygon = self.global_polygon();
+        if polygon.is_empty() {
             let position = self.base().get_global_position();
             return (position.y, position.y);
-        };
-        let position = collision_polygon.get_global_position();
+        }
         let global_center = self.global_center();
         let width_radius = self.width_radius();
-        let polygon = collision_polygon.get_polygon().to_vec();
-        let x = player_position.x;
-
-        // Handle edges
-        if x < global_center.x - width_radius {
-            // Calculate leftmost vertex
-            if let Some((i, v)) = polygon
-                .iter()
-                .enumerate()
-                .map(|(i, v)| (i, *v + position))
-                .min_by(|(_, a), (_, b)| a.x.total_cmp(&b.x))
-            {
-                let prev = {
-                    if i == 0 {
-                        polygon[polygon.len() - 1]
-                    } else {
-                        polygon[(i - 1) % polygon.len()]
-                    }
-                } + position;
-                let next = polygon[(i + 1) % polygon.len()] + position;
-                let closest = if (prev.x - v.x).abs() < (next.x - v.x).abs() {
-                    prev
-                } else {
-                    next
-                };
-                if (closest.x - v.x).abs() < 0.1 {
-                    let bottom = v.y.max(closest.y);
-                    let top = v.y.min(closest.y);
-                    return (top, bottom);
-                }
-                return (v.y, v.y);
-            }
-            return (position.y, position.y);
-        }
-        if x > global_center.x + width_radius {
-            // Calculate rightmost vertex
-            if let Some((i, v)) = polygon
-                .iter()
-                .enumerate()
-                .map(|(i, v)| (i, *v + position))
-                .max_by(|(_, a), (_, b)| a.x.total_cmp(&b.x))
-            {
-                let prev = {
-                    if i == 0 {
-                        polygon[polygon.len() - 1]
-                    } else {
-                        polygon[(i - 1) % polygon.len()]
-                    }
-                } + position;
-                let next = polygon[(i + 1) % polygon.len()] + position;
-                let closest = if (prev.x - v.x).abs() < (next.x - v.x).abs() {
-                    prev
-                } else {
-                    next
-                };
-                if (closest.x - v.x).abs() < 0.1 {
-                    let bottom = v.y.max(closest.y);
-                    let top = v.y.min(closest.y);
-                    return (top, bottom);
-                }
-                return (v.y, v.y);
-            }
-            return (position.y, position.y);
-        }
+        // Outside of the object the contour of the nearest edge is used, so that the sides are solid
+        let x = player_position.x.clamp(
+            global_center.x - width_radius,
+            global_center.x + width_radius,
+        );
 
         // Calculate the lowest y on an edge containing player position x
         // Calculate the highest y on an edge containing player position x
@@ -193,8 +177,8 @@ impl SlopedSolidObject {
         for i in 0..polygon.len() {
             let next_index = (i + 1) % polygon.len();
             // Edge positions in global space
-            let mut point = polygon[i] + position;
-            let mut next_point = polygon[next_index] + position;
+            let mut point = polygon[i];
+            let mut next_point = polygon[next_index];
             if next_point.x < point.x {
                 std::mem::swap(&mut point, &mut next_point);
             }
@@ -219,49 +203,47 @@ impl SlopedSolidObject {
         (min, max)
     }
 
-    fn polygon_center(&self) -> Vector2 {
-        let (min_x, max_x) = self.min_max_x();
-        let (min_y, max_y) = self.min_max_y();
-        Vector2::new((min_x + max_x) * 0.5, (min_y + max_y) * 0.5)
+    /// Collision polygon in global space, so that rotating or scaling the object changes its shape
+    fn global_polygon(&self) -> Vec<Vector2> {
+        let Some(collision_polygon) = &self.collision_polygon else {
+            return vec![];
+        };
+        let transform = collision_polygon.get_global_transform();
+        collision_polygon
+            .get_polygon()
+            .as_slice()
+            .iter()
+            .map(|point| transform * *point)
+            .collect()
     }
     fn min_max_y(&self) -> (f32, f32) {
-        if let Some(collision_polygon) = &self.collision_polygon {
-            let polygon = collision_polygon.get_polygon().to_vec();
-            let min = polygon
-                .iter()
-                .map(|a| a.y)
-                .min_by(f32::total_cmp)
-                .unwrap_or_default();
-
-            let max = polygon
-                .iter()
-                .map(|a| a.y)
-                .max_by(f32::total_cmp)
-                .unwrap_or_default();
-
-            return (min, max);
-        }
-        (0.0, 0.0)
+        let polygon = self.global_polygon();
+        let min = polygon
+            .iter()
+            .map(|a| a.y)
+            .min_by(f32::total_cmp)
+            .unwrap_or_default();
+        let max = polygon
+            .iter()
+            .map(|a| a.y)
+            .max_by(f32::total_cmp)
+            .unwrap_or_default();
+        (min, max)
     }
 
     fn min_max_x(&self) -> (f32, f32) {
-        if let Some(collision_polygon) = &self.collision_polygon {
-            let polygon = collision_polygon.get_polygon().to_vec();
-            let min = polygon
-                .iter()
-                .map(|a| a.x)
-                .min_by(f32::total_cmp)
-                .unwrap_or_default();
-
-            let max = polygon
-                .iter()
-                .map(|a| a.x)
-                .max_by(f32::total_cmp)
-                .unwrap_or_default();
-
-            return (min, max);
-        }
-        (0.0, 0.0)
+        let polygon = self.global_polygon();
+        let min = polygon
+            .iter()
+            .map(|a| a.x)
+            .min_by(f32::total_cmp)
+            .unwrap_or_default();
+        let max = polygon
+            .iter()
+            .map(|a| a.x)
+            .max_by(f32::total_cmp)
+            .unwrap_or_default();
+        (min, max)
     }
 
     pub fn width_radius(&self) -> f32 {
@@ -1,29 +1,251 @@
+// The code generated by `godot_api` for `set_property` checks a cfg unknown to this crate
+#![allow(unexpected_cfgs)]
+mod bake;
+
 use godot::{
-    classes::{CollisionPolygon2D, IPath2D, Path2D},
+    classes::{
+        CollisionObject2D, CollisionPolygon2D, Curve2D, IPath2D, Path2D, StaticBody2D, TileMapLayer,
+    },
     prelude::*,
 };
 
+/// Path that is solid for the sensors. With no `thickness` the curve is the outline of the solid area,
+/// otherwise it is the surface of a strip, which can be split into segments on different collision layers.
+/// The collision is rebuilt when the curve changes
 #[derive(GodotClass)]
 #[class(tool,init, base=Path2D)]
 pub struct SolidPath2D {
+    /// Collision polygon of the whole path, its static body sets the collision layer
     #[export]
     shape: Option<Gd<CollisionPolygon2D>>,
-
-    old_points: PackedVector2Array,
+    /// Thickness of the strip under the curve (going from left to right), a closed curve makes a ring.
+    /// With 0 the curve is closed into a polygon
+    #[export(range = (0.0, 64.0, 1.0, or_greater))]
+    #[var(get, set = set_thickness)]
+    thickness: f32,
+    /// Top solid path, the sensors report it as `Solidity::Top` and the characters go through it from below
+    #[export]
+    #[var(get, set = set_one_way)]
+    one_way: bool,
+    /// Collision layer of each segment of a strip, from each point of the curve to the next.
+    /// Segments get their own static body, the ones without a layer (or 0) use the layer of `shape`
+    #[export]
+    #[var(get, set = set_segment_layers)]
+    segment_layers: PackedInt32Array,
+    /// Layer where the collision is baked into 16x16 tiles, which keep the one way collision and the segment layers
+    #[export]
+    bake_target: Option<Gd<TileMapLayer>>,
+    /// Check to bake the collision into `bake_target`, the path can be removed afterwards
+    #[export]
+    #[var(get, set = set_bake)]
+    bake: bool,
+    /// Static bodies generated for `segment_layers`, they are not saved with the scene
+    segment_bodies: Vec<Gd<StaticBody2D>>,
+    /// Curve whose `changed` signal rebuilds the collision
+    connected_curve: Option<Gd<Curve2D>>,
     base: Base<Path2D>,
 }
 
 #[godot_api]
 impl IPath2D for SolidPath2D {
-    fn physics_process(&mut self, _delta: f64) {
-        if let Some(curve) = self.base().get_curve() {
-            if let Some(shape) = &mut self.shape {
-                let points = curve.get_baked_points();
-                if points != self.old_points {
-                    self.old_points = points.clone();
-                    shape.set_polygon(&points);
-                }
-            };
+    fn ready(&mut self) {
+        let curve = self.base().get_curve();
+        self.connect_curve(curve);
+        self.rebuild();
+    }
+    fn set_property(&mut self, property: StringName, value: Variant) -> bool {
+        // Called before `Path2D` replaces the curve (from the inspector or `set`), which still handles it
+        if property == StringName::from("curve") {
+            self.connect_curve(value.try_to::<Gd<Curve2D>>().ok());
+            if self.base().is_node_ready() {
+                self.base_mut().call_deferred("rebuild", &[]);
+            }
+        }
+        false
+    }
+}
+
+#[godot_api]
+impl SolidPath2D {
+    #[func]
+    fn set_thickness(&mut self, value: f32) {
+        self.thickness = value;
+        self.rebuild_if_ready();
+    }
+    #[func]
+    fn set_one_way(&mut self, value: bool) {
+        self.one_way = value;
+        self.rebuild_if_ready();
+    }
+    #[func]
+    fn set_segment_layers(&mut self, value: PackedInt32Array) {
+        self.segment_layers = value;
+        self.rebuild_if_ready();
+    }
+    #[func]
+    fn set_bake(&mut self, value: bool) {
+        if !value || !self.base().is_inside_tree() {
+            return;
+        }
+        if let Some(target) = self.bake_target.clone() {
+            self.bake_tiles(target);
+        } else {
+            godot_warn!("Set the bake target of {}", self.base().get_name());
+        }
+    }
+    /// Rebuilds the collision polygons from the curve
+    #[func]
+    fn rebuild(&mut self) {
+        for mut body in self.segment_bodies.drain(..) {
+            body.queue_free();
+        }
+        let Some(curve) = self.base().get_curve() else {
+            return;
+        };
+        let segments = segment_points(&curve);
+        let use_segments = self.thickness > 0.0 && !self.segment_layers.is_empty();
+        let polygon = if self.thickness > 0.0 {
+            let mut points: Vec<Vector2> = vec![];
+            for segment in &segments {
+                // Segments share their end points
+                let skip = usize::from(!points.is_empty());
+                points.extend(segment.iter().skip(skip));
+            }
+            self.strip(&points)
+        } else {
+            curve.get_baked_points()
         };
+        let one_way = self.one_way;
+        let default_layer = self.shape_layer();
+        if let Some(shape) = &mut self.shape {
+            shape.set_polygon(&polygon);
+            shape.set_one_way_collision(one_way);
+            shape.set_disabled(use_segments);
+        }
+        if !use_segments {
+            return;
+        }
+        for (index, points) in segments.iter().enumerate() {
+            let layer = self
+                .segment_layers
+                .get(index)
+                .filter(|layer| *layer > 0)
+                .map_or(default_layer, i32::unsigned_abs);
+            let mut polygon = CollisionPolygon2D::new_alloc();
+            polygon.set_polygon(&self.strip(points));
+            polygon.set_one_way_collision(one_way);
+            let mut body = StaticBody2D::new_alloc();
+            body.set_name(&format!("Segment{index}"));
+            body.set_collision_layer(layer);
+            body.add_child(&polygon);
+            self.base_mut().add_child(&body);
+            self.segment_bodies.push(body);
+        }
     }
 }
+
+impl SolidPath2D {
+    /// Path with a static body on `collision_layer`, used by generators like `Loop`.
+    /// The curve must go around the solid area, its baked points are the collision polygon
+    pub(crate) fn create(curve: &Gd<Curve2D>, collision_layer: u32) -> Gd<Self> {
+        let mut polygon = CollisionPolygon2D::new_alloc();
+        polygon.set_name("CollisionPolygon2D");
+        polygon.set_polygon(&curve.get_baked_points());
+        let mut body = StaticBody2D::new_alloc();
+        body.set_name("StaticBody2D");
+        body.set_collision_layer(collision_layer);
+        body.add_child(&polygon);
+        let mut path = Self::new_alloc();
+        path.set_curve(curve);
+        path.add_child(&body);
+        path.bind_mut().shape = Some(polygon);
+        path
+    }
+
+    /// Moves the `changed` connection from the previous curve to `curve`
+    fn connect_curve(&mut self, curve: Option<Gd<Curve2D>>) {
+        if self.connected_curve == curve {
+            return;
+        }
+        let rebuild = self.base().callable("rebuild");
+        if let Some(mut previous) = self.connected_curve.take() {
+            if previous.is_connected("changed", &rebuild) {
+                previous.disconnect("changed", &rebuild);
+            }
+        }
+        if let Some(mut curve) = curve.clone() {
+            curve.connect("changed", &rebuild);
+        }
+        self.connected_curve = curve;
+    }
+
+    fn rebuild_if_ready(&mut self) {
+        if self.base().is_node_ready() {
+            self.rebuild();
+        }
+    }
+
+    /// Collision layer of the body of `shape`, the first layer if there is none
+    fn shape_layer(&self) -> u32 {
+        self.shape.as_ref().map_or(1, body_layer)
+    }
+
+    /// Polygon of the strip of `thickness` under the line going through `points`.
+    /// When the line is closed it goes around the ring and back through the seam
+    fn strip(&self, points: &[Vector2]) -> PackedVector2Array {
+        let count = points.len();
+        if count < 2 {
+            return PackedVector2Array::new();
+        }
+        let closed = count > 2 && points[0].distance_to(points[count - 1]) < 0.5;
+        // Goes back along the bottom of the strip
+        let bottom = (0..count).rev().map(|i| {
+            let previous = match i {
+                0 if closed => points[count - 2],
+                0 => points[0],
+                _ => points[i - 1],
+            };
+            let next = if i + 1 < count {
+                points[i + 1]
+            } else if closed {
+                points[1]
+            } else {
+                points[i]
+            };
+            // The orthogonal of a tangent going right points up
+            let tangent = (next - previous).normalized();
+            points[i] - tangent.orthogonal() * self.thickness
+        });
+        let polygon: Vec<Vector2> = points.iter().copied().chain(bottom).collect();
+        PackedVector2Array::from(polygon.as_slice())
+    }
+}
+
+/// Collision layer of the body of `shape`, the first layer if it has none
+fn body_layer(shape: &Gd<CollisionPolygon2D>) -> u32 {
+    shape
+        .get_parent()
+        .and_then(|parent| parent.try_cast::<CollisionObject2D>().ok())
+        .map_or(1, |body| body.get_collision_layer())
+}
+
+/// Points along each segment of the curve, from each point to the next, spaced by about the bake interval
+fn segment_points(curve: &Gd<Curve2D>) -> Vec<Vec<Vector2>> {
+    let interval = curve.get_bake_interval().max(1.0);
+    (0..curve.get_point_count() - 1)
+        .map(|index| {
+            let from = curve.get_point_position(index);
+            let to = curve.get_point_position(index + 1);
+            // Upper bound of the length of the bezier curve
+            let length = curve.get_point_out(index).length()
+                + (to - from).length()
+                + curve.get_point_in(index + 1).length();
+            #[allow(clippy::cast_possible_truncation)]
+            let steps = (length / interval).ceil().max(1.0) as i32;
+            #[allow(clippy::cast_precision_loss)]
+            (0..=steps)
+                .map(|step| curve.sample(index, step as f32 / steps as f32))
+                .collect()
+        })
+        .collect()
+}
@@ -0,0 +1,439 @@
+use std::collections::HashMap;
+
+use godot::{
+    classes::{
+        image::Format, CollisionPolygon2D, Geometry2D, Image, ImageTexture, TileMapLayer, TileSet,
+        TileSetAtlasSource,
+    },
+    prelude::*,
+};
+
+use super::{body_layer, SolidPath2D};
+
+const TILE_SIZE: i32 = 16;
+/// Tiles in each row of the atlas texture
+const ATLAS_COLUMNS: i32 = 16;
+
+/// Collision of one baked tile, relative to the center of the tile like the polygons of `TileData`
+struct BakedTile {
+    /// Polygons with the physics layer of the tile set they go in
+    polygons: Vec<(i32, Vec<Vector2>)>,
+    /// From <https://info.sonicretro.org/SPG:Solid_Tiles#Height_Array>
+    /// Height of the solid part of each column, from the bottom of the tile
+    height_mask: Vec<i32>,
+    full: bool,
+}
+
+impl SolidPath2D {
+    /// From <https://info.sonicretro.org/SPG:Solid_Tiles>
+    /// Rasterizes the collision of the path into 16x16 tiles of `target`, each different tile gets its collision polygons
+    /// and height mask in a new atlas source of the tile set, which replaces the one of the previous bake.
+    /// Each collision layer of the path goes in the physics layer of the tile set with the same collision layer
+    pub(super) fn bake_tiles(&mut self, mut target: Gd<TileMapLayer>) {
+        let shapes = self.collision_polygons(&target);
+        if shapes.is_empty() {
+            godot_warn!("Nothing to bake in {}", self.base().get_name());
+            return;
+        }
+        let mut tile_set = target.get_tile_set().unwrap_or_else(|| {
+            let mut tile_set = TileSet::new_gd();
+            tile_set.set_tile_size(Vector2i::new(TILE_SIZE, TILE_SIZE));
+            target.set_tile_set(&tile_set);
+            tile_set
+        });
+        let polygons: Vec<(i32, PackedVector2Array)> = shapes
+            .into_iter()
+            .map(|(layer, polygon)| (physics_layer(&mut tile_set, layer), polygon))
+            .collect();
+        add_custom_data_layer(
+            &mut tile_set,
+            "height_mask",
+            VariantType::PACKED_INT32_ARRAY,
+        );
+        add_custom_data_layer(&mut tile_set, "snap", VariantType::BOOL);
+
+        let source_name = format!("{} bake", self.base().get_name());
+        remove_baked_source(&mut target, &mut tile_set, &source_name);
+
+        // Same tiles share the same atlas coordinates
+        let mut tiles: Vec<BakedTile> = vec![];
+        let mut tile_indices: HashMap<Vec<(i32, i32)>, usize> = HashMap::new();
+        let mut cells: Vec<(Vector2i, usize)> = vec![];
+        let (from, to) = cell_bounds(&target, &polygons);
+        for y in from.y..=to.y {
+            for x in from.x..=to.x {
+                let coords = Vector2i::new(x, y);
+                let center = target.map_to_local(coords);
+                let Some(tile) = bake_cell(&polygons, center) else {
+                    continue;
+                };
+                let key = tile_key(&tile);
+                let index = *tile_indices.entry(key).or_insert_with(|| {
+                    tiles.push(tile);
+                    tiles.len() - 1
+                });
+                cells.push((coords, index));
+            }
+        }
+
+        let Some(mut source) = atlas_source(&tiles) else {
+            return;
+        };
+        source.set_name(&source_name);
+        let source_id = tile_set.add_source(&source);
+        for (index, tile) in tiles.iter().enumerate() {
+            let atlas_coords = atlas_coords(index);
+            source.create_tile(atlas_coords);
+            let Some(mut tile_data) = source.get_tile_data(atlas_coords, 0) else {
+                continue;
+            };
+            for (layer, polygon) in &tile.polygons {
+                let polygon_index = tile_data.get_collision_polygons_count(*layer);
+                tile_data.add_collision_polygon(*layer);
+                tile_data.set_collision_polygon_points(
+                    *layer,
+                    polygon_index,
+                    &PackedVector2Array::from(polygon.as_slice()),
+                );
+                tile_data.set_collision_polygon_one_way(*layer, polygon_index, self.one_way);
+            }
+            let height_mask = PackedInt32Array::from(tile.height_mask.as_slice());
+            tile_data.set_custom_data("height_mask", &height_mask.to_variant());
+            tile_data.set_custom_data("snap", &tile.full.to_variant());
+        }
+        for (coords, index) in &cells {
+            target
+                .set_cell_ex(*coords)
+                .source_id(source_id)
+                .atlas_coords(atlas_coords(*index))
+                .done();
+        }
+        godot_print!("Baked {} cells with {} tiles", cells.len(), tiles.len());
+    }
+
+    /// Enabled collision polygons of the path with the collision layer of their body, in the local coordinates of `target`
+    fn collision_polygons(&self, target: &Gd<TileMapLayer>) -> Vec<(u32, PackedVector2Array)> {
+        let shapes = self.shape.iter().cloned().chain(
+            self.segment_bodies
+                .iter()
+                .filter_map(|body| body.get_child(0))
+                .filter_map(|child| child.try_cast::<CollisionPolygon2D>().ok()),
+        );
+        let to_target = target.get_global_transform().affine_inverse();
+        shapes
+            .filter(|shape| !shape.is_disabled() && shape.get_polygon().len() > 2)
+            .map(|shape| {
+                let transform = to_target * shape.get_global_transform();
+                let points: Vec<Vector2> = shape
+                    .get_polygon()
+                    .as_slice()
+                    .iter()
+                    .map(|point| transform * *point)
+                    .collect();
+                (
+                    body_layer(&shape),
+                    PackedVector2Array::from(points.as_slice()),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Physics layer of `tile_set` with `collision_layer`, added if there is none
+fn physics_layer(tile_set: &mut Gd<TileSet>, collision_layer: u32) -> i32 {
+    let count = tile_set.get_physics_layers_count();
+    if let Some(layer) = (0..count)
+        .find(|layer| tile_set.get_physics_layer_collision_layer(*layer) == collision_layer)
+    {
+        return layer;
+    }
+    tile_set.add_physics_layer();
+    tile_set.set_physics_layer_collision_layer(count, collision_layer);
+    count
+}
+
+/// First and last cells covered by `polygons`
+fn cell_bounds(
+    target: &Gd<TileMapLayer>,
+    polygons: &[(i32, PackedVector2Array)],
+) -> (Vector2i, Vector2i) {
+    let mut points = polygons.iter().flat_map(|(_, polygon)| polygon.to_vec());
+    let first = points.next().unwrap_or_default();
+    let (min, max) = points.fold((first, first), |(min, max), point| {
+        (min.coord_min(point), max.coord_max(point))
+    });
+    (target.local_to_map(min), target.local_to_map(max))
+}
+
+/// Intersection of `polygons` with the cell at `center`, `None` when it is empty
+fn bake_cell(polygons: &[(i32, PackedVector2Array)], center: Vector2) -> Option<BakedTile> {
+    #[allow(clippy::cast_precision_loss)]
+    let half = TILE_SIZE as f32 / 2.0;
+    let cell = PackedVector2Array::from(&[
+        Vector2::new(-half, -half),
+        Vector2::new(half, -half),
+        Vector2::new(half, half),
+        Vector2::new(-half, half),
+    ]);
+    let mut geometry = Geometry2D::singleton();
+    let mut pieces: Vec<(i32, Vec<Vector2>)> = vec![];
+    for (layer, polygon) in polygons {
+        let local: Vec<Vector2> = polygon
+            .as_slice()
+            .iter()
+            .map(|point| *point - center)
+            .collect();
+        let local = PackedVector2Array::from(local.as_slice());
+        for piece in geometry.intersect_polygons(&local, &cell).iter_shared() {
+            // Rounds to a quarter of pixel so that the same tiles are shared
+            let rounded: Vec<Vector2> = piece
+                .as_slice()
+                .iter()
+                .map(|point| (*point * 4.0).round() / 4.0)
+                .collect();
+            if polygon_area(&rounded) >= 0.5 {
+                pieces.push((*layer, rounded));
+            }
+        }
+    }
+    if pieces.is_empty() {
+        return None;
+    }
+    let pieces = merge_pieces(&mut geometry, pieces);
+    let full = pieces.len() == 1 && polygon_area(&pieces[0].1) >= 4.0 * half * half - 0.5;
+    // Full tiles use the same polygon as the level tiles, which the sensors flag as snapped
+    let pieces = if full {
+        vec![(pieces[0].0, cell.to_vec())]
+    } else {
+        pieces
+    };
+
+    let height_mask: Vec<i32> = (0..TILE_SIZE)
+        .map(|column| {
+            #[allow(clippy::cast_precision_loss)]
+            let x = column as f32 - half + 0.5;
+            (0..TILE_SIZE)
+                .find(|row| {
+                    #[allow(clippy::cast_precision_loss)]
+                    let point = Vector2::new(x, *row as f32 - half + 0.5);
+                    pieces.iter().any(|(_, piece)| {
+                        geometry
+                            .is_point_in_polygon(point, &PackedVector2Array::from(piece.as_slice()))
+                    })
+                })
+                .map_or(0, |row| TILE_SIZE - row)
+        })
+        .collect();
+    Some(BakedTile {
+        polygons: pieces,
+        height_mask,
+        full,
+    })
+}
+
+/// Merges the pieces on the same layer that overlap or touch, like the ones of the segments of a strip
+fn merge_pieces(
+    geometry: &mut Gd<Geometry2D>,
+    mut pieces: Vec<(i32, Vec<Vector2>)>,
+) -> Vec<(i32, Vec<Vector2>)> {
+    let mut merged: Vec<(i32, Vec<Vector2>)> = vec![];
+    while let Some((layer, mut piece)) = pieces.pop() {
+        let mut index = 0;
+        while index < pieces.len() {
+            if pieces[index].0 != layer {
+                index += 1;
+                continue;
+            }
+            let result = geometry.merge_polygons(
+                &PackedVector2Array::from(piece.as_slice()),
+                &PackedVector2Array::from(pieces[index].1.as_slice()),
+            );
+            if result.len() == 1 {
+                piece = result.at(0).to_vec();
+                pieces.swap_remove(index);
+                index = 0;
+            } else {
+                index += 1;
+            }
+        }
+        merged.push((layer, piece));
+    }
+    merged
+}
+
+/// Area of the polygon with the shoelace formula
+fn polygon_area(points: &[Vector2]) -> f32 {
+    let count = points.len();
+    (0..count)
+        .map(|i| points[i].cross(points[(i + 1) % count]))
+        .sum::<f32>()
+        .abs()
+        / 2.0
+}
+
+/// Identifies the tiles with the same polygons on the same layers
+fn tile_key(tile: &BakedTile) -> Vec<(i32, i32)> {
+    #[allow(clippy::cast_possible_truncation)]
+    tile.polygons
+        .iter()
+        .flat_map(|(layer, polygon)| {
+            polygon
+                .iter()
+                .map(|point| ((point.x * 4.0) as i32, (point.y * 4.0) as i32))
+                // Separates the polygons
+                .chain([(i32::MAX, *layer)])
+        })
+        .collect()
+}
+
+fn atlas_coords(index: usize) -> Vector2i {
+    let index = i32::try_from(index).unwrap_or_default();
+    Vector2i::new(index % ATLAS_COLUMNS, index / ATLAS_COLUMNS)
+}
+
+/// Atlas source with a texture showing the solid pixels of `tiles`
+fn atlas_source(tiles: &[BakedTile]) -> Option<Gd<TileSetAtlasSource>> {
+    let rows = atlas_coords(tiles.len().max(1) - 1).y + 1;
+    let mut image = Image::create_empty(
+        ATLAS_COLUMNS * TILE_SIZE,
+        rows * TILE_SIZE,
+        false,
+        Format::RGBA8,
+    )?;
+    let mut geometry = Geometry2D::singleton();
+    #[allow(clippy::cast_precision_loss)]
+    let half = TILE_SIZE as f32 / 2.0;
+    for (index, tile) in tiles.iter().enumerate() {
+        let origin = atlas_coords(index) * TILE_SIZE;
+        let polygons: Vec<PackedVector2Array> = tile
+            .polygons
+            .iter()
+            .map(|(_, polygon)| PackedVector2Array::from(polygon.as_slice()))
+            .collect();
+        for y in 0..TILE_SIZE {
+            for x in 0..TILE_SIZE {
+                #[allow(clippy::cast_precision_loss)]
+                let point = Vector2::new(x as f32 - half + 0.5, y as f32 - half + 0.5);
+                if polygons
+                    .iter()
+                    .any(|polygon| geometry.is_point_in_polygon(point, polygon))
+                {
+                    image.set_pixel(origin.x + x, origin.y + y, Color::WHITE);
+                }
+            }
+        }
+    }
+    let mut source = TileSetAtlasSource::new_gd();
+    if let Some(texture) = ImageTexture::create_from_image(&image) {
+        source.set_texture(&texture);
+    }
+    source.set_texture_region_size(Vector2i::new(TILE_SIZE, TILE_SIZE));
+    Some(source)
+}
+
+fn add_custom_data_layer(tile_set: &mut Gd<TileSet>, name: &str, layer_type: VariantType) {
+    if tile_set.get_custom_data_layer_by_name(name) >= 0 {
+        return;
+    }
+    let index = tile_set.get_custom_data_layers_count();
+    tile_set.add_custom_data_layer();
+    tile_set.set_custom_data_layer_name(index, name);
+    tile_set.set_custom_data_layer_type(index, layer_type);
+}
+
+/// Removes the atlas source of a previous bake and its cells
+fn remove_baked_source(target: &mut Gd<TileMapLayer>, tile_set: &mut Gd<TileSet>, name: &str) {
+    let ids: Vec<i32> = (0..tile_set.get_source_count())
+        .map(|index| tile_set.get_source_id(index))
+        .filter(|id| {
+            tile_set
+                .get_source(*id)
+                .is_some_and(|source| source.get_name().to_string() == name)
+        })
+        .collect();
+    for id in ids {
+        for coords in target
+            .get_used_cells_by_id_ex()
+            .source_id(id)
+            .done()
+            .iter_shared()
+        {
+            target.erase_cell(coords);
+        }
+        tile_set.remove_source(id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use godot::builtin::math::assert_eq_approx;
+    use godot::prelude::*;
+
+    use super::{atlas_coords, polygon_area, tile_key, BakedTile, ATLAS_COLUMNS};
+
+    fn tile(polygons: Vec<(i32, Vec<Vector2>)>) -> BakedTile {
+        BakedTile {
+            polygons,
+            height_mask: vec![],
+            full: false,
+        }
+    }
+
+    fn square(size: f32) -> Vec<Vector2> {
+        vec![
+            Vector2::new(0.0, 0.0),
+            Vector2::new(size, 0.0),
+            Vector2::new(size, size),
+            Vector2::new(0.0, size),
+        ]
+    }
+
+    #[test]
+    fn polygon_area_ignores_winding() {
+        let square = square(16.0);
+        assert_eq_approx!(polygon_area(&square), 256.0);
+        let reversed: Vec<Vector2> = square.into_iter().rev().collect();
+        assert_eq_approx!(polygon_area(&reversed), 256.0);
+        let triangle = [
+            Vector2::new(-8.0, 8.0),
+            Vector2::new(8.0, 8.0),
+            Vector2::new(8.0, -8.0),
+        ];
+        assert_eq_approx!(polygon_area(&triangle), 128.0);
+        assert_eq_approx!(polygon_area(&[]), 0.0);
+    }
+
+    #[test]
+    fn tile_key_matches_same_tiles() {
+        let a = tile(vec![(0, square(8.0))]);
+        let b = tile(vec![(0, square(8.0))]);
+        assert_eq!(tile_key(&a), tile_key(&b));
+        // Quarter pixels are told apart
+        let c = tile(vec![(0, square(8.25))]);
+        assert_ne!(tile_key(&a), tile_key(&c));
+    }
+
+    #[test]
+    fn tile_key_separates_layers_and_polygons() {
+        let layer_0 = tile(vec![(0, square(8.0))]);
+        let layer_1 = tile(vec![(1, square(8.0))]);
+        assert_ne!(tile_key(&layer_0), tile_key(&layer_1));
+        let points = square(8.0);
+        let one = tile(vec![(0, points.clone())]);
+        let split = tile(vec![(0, points[..2].to_vec()), (0, points[2..].to_vec())]);
+        assert_ne!(tile_key(&one), tile_key(&split));
+    }
+
+    #[test]
+    fn atlas_coords_fill_rows() {
+        assert_eq!(atlas_coords(0), Vector2i::new(0, 0));
+        assert_eq!(atlas_coords(1), Vector2i::new(1, 0));
+        let columns = usize::try_from(ATLAS_COLUMNS).unwrap_or_default();
+        assert_eq!(
+            atlas_coords(columns - 1),
+            Vector2i::new(ATLAS_COLUMNS - 1, 0)
+        );
+        assert_eq!(atlas_coords(columns), Vector2i::new(0, 1));
+        assert_eq!(atlas_coords(columns * 2 + 3), Vector2i::new(3, 2));
+    }
+}
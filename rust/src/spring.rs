@@ -0,0 +1,310 @@
+#![allow(clippy::needless_pass_by_value)]
+pub mod diagonal_spring;
+
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use godot::{
+    classes::{AnimatedSprite2D, Engine},
+    prelude::*,
+};
+
+use crate::{
+    character::{airborne::AirPhysics, Character},
+    solid_object::{Collision, CollisionInfo, SolidObject},
+    tool::Direction,
+};
+
+#[derive(GodotConvert, Var, Export, Default, Debug, PartialEq, Eq, Clone, Copy)]
+#[godot(via = GString)]
+pub enum SpringStrength {
+    /// Launches at 10 pixels per frame
+    #[default]
+    Yellow,
+    /// Launches at 16 pixels per frame
+    Red,
+}
+
+impl SpringStrength {
+    pub const fn force(self) -> f32 {
+        match self {
+            Self::Yellow => 10.0,
+            Self::Red => 16.0,
+        }
+    }
+}
+
+/// Plays the compressed animation of the spring, keeping the color suffix of the current animation
+fn play_spring_animation(sprite: &mut Gd<AnimatedSprite2D>) {
+    let animation = sprite
+        .get_animation()
+        .to_string()
+        .replace("relaxed", "spring");
+    sprite.play_ex().name(animation.as_str()).done();
+}
+
+/// Goes back to the relaxed animation once the compressed one finished
+fn relax_spring_animation(sprite: &mut Gd<AnimatedSprite2D>) {
+    let animation = sprite.get_animation().to_string();
+    if animation.starts_with("spring") {
+        sprite
+            .play_ex()
+            .name(animation.replace("spring", "relaxed").as_str())
+            .done();
+    }
+}
+
+/// Draws the path of a character launched from `position` with `velocity` without input.
+/// When running it is predicted by `Character::predict_trajectory` of `preview_character`, the characters
+/// don't run in the editor so it uses the air physics of the default character there or when none is set
+fn draw_trajectory(
+    base: &mut Gd<Node2D>,
+    preview_character: Option<&Gd<Character>>,
+    position: Vector2,
+    velocity: Vector2,
+    frames: i32,
+) {
+    let points: Vec<Vector2> = match preview_character {
+        Some(character) if !Engine::singleton().is_editor_hint() => {
+            let path = character
+                .bind()
+                .predict_trajectory(velocity, frames, 0.0, false);
+            let origin = path.get(0).unwrap_or_default();
+            path.as_slice()
+                .iter()
+                .map(|point| position + *point - origin)
+                .collect()
+        }
+        _ => AirPhysics::default().trajectory(position, velocity, 0.0, frames),
+    };
+    for pair in points.windows(2) {
+        base.draw_dashed_line_ex(pair[0], pair[1], Color::WHITE)
+            .width(0.5)
+            .done();
+    }
+}
+
+/// From <https://info.sonicretro.org/SPG:Game_Objects#Springs>
+/// Vertical and horizontal springs, the collision is done by the child `solid_object`, which is resized with the direction
+#[derive(GodotClass)]
+#[class(tool, init, base=Node2D)]
+pub struct Spring {
+    #[export]
+    solid_object: Option<Gd<SolidObject>>,
+    #[export]
+    sprite: Option<Gd<AnimatedSprite2D>>,
+    #[export]
+    #[var(get, set = set_direction)]
+    direction: Direction,
+    #[export]
+    #[var(get, set = set_strength)]
+    strength: SpringStrength,
+    /// Upward springs play the twirl animation of the character (Sonic 3)
+    #[export]
+    twirl: bool,
+    /// Draws the path of a character launched by the spring in the editor
+    #[export]
+    #[var(get, set = set_debug_trajectory)]
+    debug_trajectory: bool,
+    #[export]
+    #[init(val = 64)]
+    trajectory_frames: i32,
+    /// Character whose air physics the trajectory uses, the default ones when not set
+    #[export]
+    preview_character: Option<Gd<Character>>,
+    base: Base<Node2D>,
+}
+
+#[godot_api]
+impl INode2D for Spring {
+    fn ready(&mut self) {
+        self.update_sprite();
+        if Engine::singleton().is_editor_hint() {
+            return;
+        }
+        self.update_solid_object();
+        let collided = self.base().callable("on_collided");
+        if let Some(solid_object) = &mut self.solid_object {
+            solid_object.connect("collided", &collided);
+        }
+        let finished = self.base().callable("on_animation_finished");
+        if let Some(sprite) = &mut self.sprite {
+            sprite.connect("animation_finished", &finished);
+        }
+    }
+    fn draw(&mut self) {
+        if !self.debug_trajectory || !self.is_vertical() {
+            return;
+        }
+        let velocity = self.launch_direction() * self.force();
+        let frames = self.trajectory_frames;
+        let preview_character = self.preview_character.clone();
+        draw_trajectory(
+            &mut self.base_mut(),
+            preview_character.as_ref(),
+            Vector2::ZERO,
+            velocity,
+            frames,
+        );
+    }
+}
+
+#[godot_api]
+impl Spring {
+    /// Emitted after launching a character
+    #[signal]
+    fn launched(player: Gd<Character>);
+
+    #[func]
+    fn set_direction(&mut self, value: Direction) {
+        self.direction = value;
+        self.update_sprite();
+        self.base_mut().queue_redraw();
+    }
+    #[func]
+    fn set_strength(&mut self, value: SpringStrength) {
+        self.strength = value;
+        self.base_mut().queue_redraw();
+    }
+    #[func]
+    fn set_debug_trajectory(&mut self, value: bool) {
+        self.debug_trajectory = value;
+        self.base_mut().queue_redraw();
+    }
+    #[func]
+    fn on_collided(
+        &mut self,
+        collision: Collision,
+        player: Gd<Character>,
+        _info: Gd<CollisionInfo>,
+    ) {
+        let mut player = player;
+        if self.collision_matches_direction(collision) {
+            if self.is_vertical() {
+                self.vertical_spring(&mut player);
+            } else {
+                self.horizontal_spring(&mut player);
+            }
+        } else if !self.is_vertical()
+            && self.player_not_moving_towards_spring(&player)
+            && self.check_box_around_player(&player)
+        {
+            self.horizontal_spring(&mut player);
+        }
+    }
+    #[func]
+    fn on_animation_finished(&mut self) {
+        if let Some(sprite) = &mut self.sprite {
+            relax_spring_animation(sprite);
+        }
+    }
+}
+
+impl Spring {
+    const fn force(&self) -> f32 {
+        self.strength.force()
+    }
+    const fn is_vertical(&self) -> bool {
+        matches!(self.direction, Direction::Up | Direction::Down)
+    }
+    const fn launch_direction(&self) -> Vector2 {
+        match self.direction {
+            Direction::Up => Vector2::UP,
+            Direction::Right => Vector2::RIGHT,
+            Direction::Down => Vector2::DOWN,
+            Direction::Left => Vector2::LEFT,
+        }
+    }
+    const fn collision_matches_direction(&self, collision: Collision) -> bool {
+        matches!(
+            (collision, self.direction),
+            (Collision::Up, Direction::Up)
+                | (Collision::Down, Direction::Down)
+                | (Collision::Left, Direction::Left)
+                | (Collision::Right, Direction::Right)
+        )
+    }
+
+    fn vertical_spring(&mut self, player: &mut Gd<Character>) {
+        let force = self.force();
+        let mut position = player.get_global_position();
+        if self.direction == Direction::Up {
+            let velocity = Vector2::new(player.bind().get_velocity().x, -force);
+            player.bind_mut().spring_bounce(velocity, self.twirl);
+            position.y += 8.0;
+        } else {
+            let mut velocity = player.bind().get_velocity();
+            velocity.y = force;
+            player.bind_mut().set_velocity(velocity);
+            position.y -= 8.0;
+        }
+        player.set_global_position(position);
+        godot_print!("Vertical spring");
+        self.launched(player);
+    }
+
+    fn horizontal_spring(&mut self, player: &mut Gd<Character>) {
+        let speed = self.launch_direction().x * self.force();
+        player.bind_mut().spring_sideways(speed);
+        godot_print!("Horizontal spring");
+        self.launched(player);
+    }
+
+    fn launched(&mut self, player: &Gd<Character>) {
+        if let Some(sprite) = &mut self.sprite {
+            play_spring_animation(sprite);
+        }
+        self.base_mut()
+            .emit_signal("launched", &[player.to_variant()]);
+    }
+
+    /// Horizontal springs also launch characters standing next to them, as long as they are not moving towards them
+    fn check_box_around_player(&self, player: &Gd<Character>) -> bool {
+        let position = self.base().get_global_position();
+        let player_position = player.get_global_position();
+        let contains_y =
+            position.y - 24.0 <= player_position.y && player_position.y < position.y + 24.0;
+        let contains_x = if self.direction == Direction::Right {
+            position.x <= player_position.x && player_position.x <= position.x + 40.0
+        } else {
+            position.x - 40.0 <= player_position.x && player_position.x <= position.x
+        };
+        contains_y && contains_x
+    }
+
+    fn player_not_moving_towards_spring(&self, player: &Gd<Character>) -> bool {
+        let velocity = player.bind().get_velocity();
+        if velocity.x == 0.0 {
+            return true;
+        }
+        let delta = player.get_global_position() - self.base().get_global_position();
+        // Moving away from the spring on either side
+        (velocity.x > 0.0 && delta.x > 0.0) || (velocity.x < 0.0 && delta.x < 0.0)
+    }
+
+    fn update_sprite(&mut self) {
+        let (rotation, position) = match self.direction {
+            Direction::Up => (0.0, Vector2::new(0.0, -8.0)),
+            Direction::Right => (FRAC_PI_2, Vector2::new(8.0, 0.0)),
+            Direction::Down => (PI, Vector2::new(0.0, 8.0)),
+            Direction::Left => (-FRAC_PI_2, Vector2::new(-8.0, 0.0)),
+        };
+        if let Some(sprite) = &mut self.sprite {
+            sprite.set_rotation(rotation);
+            sprite.set_position(position);
+        }
+    }
+
+    /// Horizontal springs are taller than wide
+    fn update_solid_object(&mut self) {
+        let (width_radius, height_radius) = if self.is_vertical() {
+            (16.0, 8.0)
+        } else {
+            (8.0, 14.0)
+        };
+        if let Some(solid_object) = &mut self.solid_object {
+            let mut solid_object = solid_object.bind_mut();
+            solid_object.set_width_radius(width_radius);
+            solid_object.set_height_radius(height_radius);
+        }
+    }
+}
@@ -0,0 +1,219 @@
+#![allow(clippy::needless_pass_by_value)]
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use godot::{
+    classes::{AnimatedSprite2D, CollisionPolygon2D, Engine},
+    prelude::*,
+};
+
+use crate::{
+    character::Character,
+    solid_object::{Collision, CollisionInfo},
+};
+
+use super::{draw_trajectory, play_spring_animation, relax_spring_animation, SpringStrength};
+
+/// Collision polygon of the top right spring, the other directions are flipped from it
+const TOP_RIGHT_POLYGON: [Vector2; 6] = [
+    Vector2::new(-12.0, -16.0),
+    Vector2::new(-12.0, 16.0),
+    Vector2::new(0.0, 16.0),
+    Vector2::new(20.0, 36.0),
+    Vector2::new(20.0, 4.0),
+    Vector2::new(0.0, -16.0),
+];
+
+#[derive(GodotConvert, Var, Export, Default, Debug, PartialEq, Eq, Clone, Copy)]
+#[godot(via = GString)]
+pub enum DiagonalDirection {
+    #[default]
+    TopRight,
+    TopLeft,
+    BottomRight,
+    BottomLeft,
+}
+
+impl DiagonalDirection {
+    const fn is_top(self) -> bool {
+        matches!(self, Self::TopRight | Self::TopLeft)
+    }
+    const fn is_right(self) -> bool {
+        matches!(self, Self::TopRight | Self::BottomRight)
+    }
+    const fn vector(self) -> Vector2 {
+        match self {
+            Self::TopRight => Vector2::new(1.0, -1.0),
+            Self::TopLeft => Vector2::new(-1.0, -1.0),
+            Self::BottomRight => Vector2::new(1.0, 1.0),
+            Self::BottomLeft => Vector2::new(-1.0, 1.0),
+        }
+    }
+}
+
+/// From <https://info.sonicretro.org/SPG:Game_Objects#Diagonal_Springs>
+/// The collision is done by a `SlopedSolidObject` using `collision_polygon`, which is flipped with the direction.
+/// Both speeds are set to the strength of the spring, so it launches faster than a vertical spring
+#[derive(GodotClass)]
+#[class(tool, init, base=Node2D)]
+pub struct DiagonalSpring {
+    /// Sloped solid object that emits the collisions
+    #[export]
+    sloped_solid_object: Option<Gd<Node2D>>,
+    #[export]
+    collision_polygon: Option<Gd<CollisionPolygon2D>>,
+    #[export]
+    sprite: Option<Gd<AnimatedSprite2D>>,
+    #[export]
+    #[var(get, set = set_direction)]
+    direction: DiagonalDirection,
+    #[export]
+    #[var(get, set = set_strength)]
+    strength: SpringStrength,
+    /// Upward springs play the twirl animation of the character (Sonic 3)
+    #[export]
+    twirl: bool,
+    /// Draws the path of a character launched by the spring in the editor
+    #[export]
+    #[var(get, set = set_debug_trajectory)]
+    debug_trajectory: bool,
+    #[export]
+    #[init(val = 64)]
+    trajectory_frames: i32,
+    /// Character whose air physics the trajectory uses, the default ones when not set
+    #[export]
+    preview_character: Option<Gd<Character>>,
+    base: Base<Node2D>,
+}
+
+#[godot_api]
+impl INode2D for DiagonalSpring {
+    fn ready(&mut self) {
+        self.update_direction();
+        if Engine::singleton().is_editor_hint() {
+            return;
+        }
+        let collided = self.base().callable("on_collided");
+        if let Some(object) = &mut self.sloped_solid_object {
+            object.connect("collided", &collided);
+        }
+        let finished = self.base().callable("on_animation_finished");
+        if let Some(sprite) = &mut self.sprite {
+            sprite.connect("animation_finished", &finished);
+        }
+    }
+    fn draw(&mut self) {
+        if !self.debug_trajectory {
+            return;
+        }
+        let velocity = self.direction.vector() * self.strength.force();
+        let frames = self.trajectory_frames;
+        let preview_character = self.preview_character.clone();
+        draw_trajectory(
+            &mut self.base_mut(),
+            preview_character.as_ref(),
+            Vector2::ZERO,
+            velocity,
+            frames,
+        );
+    }
+}
+
+#[godot_api]
+impl DiagonalSpring {
+    /// Emitted after launching a character
+    #[signal]
+    fn launched(player: Gd<Character>);
+
+    #[func]
+    fn set_direction(&mut self, value: DiagonalDirection) {
+        self.direction = value;
+        self.update_direction();
+        self.base_mut().queue_redraw();
+    }
+    #[func]
+    fn set_strength(&mut self, value: SpringStrength) {
+        self.strength = value;
+        self.base_mut().queue_redraw();
+    }
+    #[func]
+    fn set_debug_trajectory(&mut self, value: bool) {
+        self.debug_trajectory = value;
+        self.base_mut().queue_redraw();
+    }
+    #[func]
+    fn on_collided(
+        &mut self,
+        collision: Collision,
+        player: Gd<Character>,
+        _info: Gd<CollisionInfo>,
+    ) {
+        let launches = match collision {
+            Collision::Up => self.direction.is_top(),
+            Collision::Down => !self.direction.is_top(),
+            Collision::Left | Collision::Right => false,
+        };
+        if launches && self.check_horizontal_conditions(&player) {
+            self.spring(player);
+        }
+    }
+    #[func]
+    fn on_animation_finished(&mut self) {
+        if let Some(sprite) = &mut self.sprite {
+            relax_spring_animation(sprite);
+        }
+    }
+}
+
+impl DiagonalSpring {
+    /// The character has to be on the side the spring points to, 4 pixels past the center
+    fn check_horizontal_conditions(&self, player: &Gd<Character>) -> bool {
+        let x = self.base().get_global_position().x;
+        let player_x = player.get_global_position().x;
+        if self.direction.is_right() {
+            player_x > x - 4.0
+        } else {
+            player_x < x + 4.0
+        }
+    }
+
+    fn spring(&mut self, mut player: Gd<Character>) {
+        let vector = self.direction.vector();
+        let velocity = vector * self.strength.force();
+        if self.direction.is_top() {
+            player.bind_mut().spring_bounce(velocity, self.twirl);
+        } else {
+            player.bind_mut().set_velocity(velocity);
+            player.bind_mut().set_flip_h(velocity.x < 0.0);
+        }
+        let position = player.get_global_position() - vector * 8.0;
+        player.set_global_position(position);
+        godot_print!("Diagonal spring");
+        if let Some(sprite) = &mut self.sprite {
+            play_spring_animation(sprite);
+        }
+        self.base_mut()
+            .emit_signal("launched", &[player.to_variant()]);
+    }
+
+    /// Flips the collision polygon and places the sprite for the current direction
+    fn update_direction(&mut self) {
+        let flip = Vector2::new(
+            if self.direction.is_right() { 1.0 } else { -1.0 },
+            if self.direction.is_top() { 1.0 } else { -1.0 },
+        );
+        if let Some(collision_polygon) = &mut self.collision_polygon {
+            let points: Vec<Vector2> = TOP_RIGHT_POLYGON.iter().map(|p| *p * flip).collect();
+            collision_polygon.set_polygon(&PackedVector2Array::from(points.as_slice()));
+        }
+        let rotation = match self.direction {
+            DiagonalDirection::TopRight => 0.0,
+            DiagonalDirection::TopLeft => -FRAC_PI_2,
+            DiagonalDirection::BottomRight => FRAC_PI_2,
+            DiagonalDirection::BottomLeft => PI,
+        };
+        if let Some(sprite) = &mut self.sprite {
+            sprite.set_position(Vector2::new(10.0, -6.0) * flip);
+            sprite.set_rotation(rotation);
+        }
+    }
+}